@@ -0,0 +1,211 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(feature = "tokio")]
+use crate::AsyncTaskSender;
+use crate::TaskSender;
+
+/// Delivered to watchers of a key on every `set` of it, even one storing an
+/// equal value, and when it is removed. A `new` of `None` means the key was
+/// removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change<K, V> {
+    pub key: K,
+    pub old: Option<V>,
+    pub new: Option<V>,
+}
+
+enum Watcher<K, V> {
+    Task(TaskSender<Change<K, V>>),
+    #[cfg(feature = "tokio")]
+    AsyncTask(AsyncTaskSender<Change<K, V>>),
+}
+
+impl<K, V> Watcher<K, V> {
+    /// Returns whether the watcher is still alive.
+    fn notify(&self, change: Change<K, V>) -> bool {
+        match self {
+            Watcher::Task(sender) => sender.try_send(change).is_ok(),
+            #[cfg(feature = "tokio")]
+            Watcher::AsyncTask(sender) => sender.try_send(change).is_ok(),
+        }
+    }
+}
+
+struct Board<K, V> {
+    values: HashMap<K, V>,
+    watchers: HashMap<K, Vec<Watcher<K, V>>>,
+}
+
+/// A small shared key-value store whose changes are delivered as messages
+/// into the mailboxes of watching tasks.
+///
+/// Watchers of a key see its changes in the order they were applied.
+/// Watchers whose task is gone are dropped on the next change.
+pub struct Blackboard<K, V> {
+    board: Arc<Mutex<Board<K, V>>>,
+}
+
+impl<K, V> Clone for Blackboard<K, V> {
+    fn clone(&self) -> Self {
+        Blackboard {
+            board: self.board.clone(),
+        }
+    }
+}
+
+impl<K, V> Default for Blackboard<K, V> {
+    fn default() -> Self {
+        Blackboard {
+            board: Arc::new(Mutex::new(Board {
+                values: HashMap::new(),
+                watchers: HashMap::new(),
+            })),
+        }
+    }
+}
+
+impl<K, V> Blackboard<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        self.board.lock().unwrap().values.get(key).cloned()
+    }
+
+    /// Stores `value` under `key` and returns the previous value. Watchers
+    /// are notified even if `value` equals the previous one.
+    pub fn set(&self, key: K, value: V) -> Option<V> {
+        let mut board = self.board.lock().unwrap();
+        let old = board.values.insert(key.clone(), value.clone());
+        board.notify(key, old.clone(), Some(value));
+        old
+    }
+
+    pub fn remove(&self, key: &K) -> Option<V> {
+        let mut board = self.board.lock().unwrap();
+        let old = board.values.remove(key);
+        if old.is_some() {
+            board.notify(key.clone(), old.clone(), None);
+        }
+        old
+    }
+
+    pub fn watch(&self, key: K, sender: TaskSender<Change<K, V>>) {
+        self.add_watcher(key, Watcher::Task(sender))
+    }
+
+    #[cfg(feature = "tokio")]
+    pub fn watch_async(&self, key: K, sender: AsyncTaskSender<Change<K, V>>) {
+        self.add_watcher(key, Watcher::AsyncTask(sender))
+    }
+
+    fn add_watcher(&self, key: K, watcher: Watcher<K, V>) {
+        self.board
+            .lock()
+            .unwrap()
+            .watchers
+            .entry(key)
+            .or_default()
+            .push(watcher);
+    }
+}
+
+impl<K, V> Board<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+{
+    fn notify(&mut self, key: K, old: Option<V>, new: Option<V>) {
+        let Some(watchers) = self.watchers.get_mut(&key) else {
+            return;
+        };
+
+        watchers.retain(|watcher| {
+            watcher.notify(Change {
+                key: key.clone(),
+                old: old.clone(),
+                new: new.clone(),
+            })
+        });
+        if watchers.is_empty() {
+            self.watchers.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_task;
+
+    #[test]
+    fn test_watchers_receive_changes_in_order() {
+        let board = Blackboard::new();
+        let watcher = spawn_task(|receiver| receiver.iter().collect::<Vec<_>>());
+        board.watch("leader", watcher.sender());
+
+        board.set("leader", 1);
+        board.set("other", 7);
+        board.set("leader", 2);
+        board.remove(&"leader");
+
+        assert_eq!(board.get(&"leader"), None);
+        assert_eq!(board.get(&"other"), Some(7));
+
+        drop(board);
+        assert_eq!(
+            watcher.drain(),
+            vec![
+                Change {
+                    key: "leader",
+                    old: None,
+                    new: Some(1),
+                },
+                Change {
+                    key: "leader",
+                    old: Some(1),
+                    new: Some(2),
+                },
+                Change {
+                    key: "leader",
+                    old: Some(2),
+                    new: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gone_watchers_are_dropped() {
+        let board = Blackboard::new();
+        let watcher = spawn_task(|receiver| receiver.recv().unwrap());
+        board.watch(1, watcher.sender());
+
+        board.set(1, "first");
+        let first: Change<u32, &str> = watcher.join();
+        assert_eq!(first.new, Some("first"));
+
+        board.set(1, "second");
+        assert!(board.board.lock().unwrap().watchers.is_empty());
+    }
+
+    #[test]
+    fn test_clones_share_the_board() {
+        let board = Blackboard::new();
+        let writer = board.clone();
+
+        std::thread::spawn(move || writer.set("config", 42))
+            .join()
+            .unwrap();
+        assert_eq!(board.get(&"config"), Some(42));
+    }
+}
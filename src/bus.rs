@@ -0,0 +1,229 @@
+#[cfg(feature = "tokio")]
+use std::time::Duration;
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::Recipient;
+
+struct Subscribers<T> {
+    next_id: u64,
+    recipients: Vec<(u64, Recipient<T>)>,
+}
+
+/// Publish/subscribe keyed by message type: [`TypedBus::publish`] delivers
+/// a value of type `T` to every subscriber of `T`.
+///
+/// Subscribers whose task is gone are dropped on the next publish of their
+/// type.
+#[derive(Clone, Default)]
+pub struct TypedBus {
+    topics: Arc<Mutex<HashMap<TypeId, Box<dyn Any + Send>>>>,
+}
+
+impl TypedBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe<T, R>(&self, recipient: R)
+    where
+        T: Send + 'static,
+        R: Into<Recipient<T>>,
+    {
+        let mut topics = self.topics.lock().unwrap();
+        let subscribers = topics
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| {
+                Box::new(Subscribers::<T> {
+                    next_id: 0,
+                    recipients: Vec::new(),
+                })
+            })
+            .downcast_mut::<Subscribers<T>>()
+            .expect("topics are keyed by their type");
+
+        let id = subscribers.next_id;
+        subscribers.next_id += 1;
+        subscribers.recipients.push((id, recipient.into()));
+    }
+
+    /// Subscribes to `T`, but delivers the values in batches: a batch is
+    /// sent once it holds `max_size` values or `window` passed since its
+    /// first value arrived, whichever comes first. Must be called from
+    /// within a tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn subscribe_batched<T, R>(&self, recipient: R, max_size: usize, window: Duration)
+    where
+        T: Send + 'static,
+        R: Into<Recipient<Vec<T>>>,
+    {
+        assert!(max_size > 0, "batches need room for at least one value");
+
+        let recipient = recipient.into();
+        let batcher = crate::spawn_async_task(
+            move |mut receiver: tokio::sync::mpsc::UnboundedReceiver<T>| async move {
+                while let Some(first) = receiver.recv().await {
+                    let mut batch = vec![first];
+                    let deadline = tokio::time::Instant::now() + window;
+                    while batch.len() < max_size {
+                        match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                            Ok(Some(value)) => batch.push(value),
+                            Ok(None) | Err(_) => break,
+                        }
+                    }
+                    if recipient.try_send(batch).is_err() {
+                        // dropping the receiver unsubscribes us on the next publish
+                        return;
+                    }
+                }
+            },
+        );
+        self.subscribe(batcher.sender());
+    }
+
+    /// Returns to how many subscribers `value` was delivered.
+    pub fn publish<T: Clone + Send + 'static>(&self, value: T) -> usize {
+        // deliver outside the lock, a recipient may publish itself
+        let recipients = match self.subscribers::<T>() {
+            Some(recipients) if !recipients.is_empty() => recipients,
+            _ => return 0,
+        };
+
+        let mut gone = Vec::new();
+        let (last, rest) = recipients.split_last().expect("recipients are not empty");
+        for (id, recipient) in rest {
+            if recipient.try_send(value.clone()).is_err() {
+                gone.push(*id);
+            }
+        }
+        if last.1.try_send(value).is_err() {
+            gone.push(last.0);
+        }
+
+        if !gone.is_empty() {
+            self.unsubscribe::<T>(&gone);
+        }
+        recipients.len() - gone.len()
+    }
+
+    pub fn subscriber_count<T: Send + 'static>(&self) -> usize {
+        self.subscribers::<T>()
+            .map_or(0, |recipients| recipients.len())
+    }
+
+    fn subscribers<T: Send + 'static>(&self) -> Option<Vec<(u64, Recipient<T>)>> {
+        let topics = self.topics.lock().unwrap();
+        let subscribers = topics
+            .get(&TypeId::of::<T>())?
+            .downcast_ref::<Subscribers<T>>()
+            .expect("topics are keyed by their type");
+        Some(subscribers.recipients.clone())
+    }
+
+    fn unsubscribe<T: Send + 'static>(&self, ids: &[u64]) {
+        let mut topics = self.topics.lock().unwrap();
+        if let Some(subscribers) = topics.get_mut(&TypeId::of::<T>()) {
+            subscribers
+                .downcast_mut::<Subscribers<T>>()
+                .expect("topics are keyed by their type")
+                .recipients
+                .retain(|(id, _)| !ids.contains(id));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_task;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct UserCreated(u32);
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct UserDeleted(u32);
+
+    #[test]
+    fn test_values_reach_subscribers_of_their_type() {
+        let bus = TypedBus::new();
+        let created = spawn_task(|receiver| receiver.iter().collect::<Vec<UserCreated>>());
+        let deleted = spawn_task(|receiver| receiver.iter().collect::<Vec<UserDeleted>>());
+        bus.subscribe(created.sender());
+        bus.subscribe(deleted.sender());
+
+        assert_eq!(bus.publish(UserCreated(1)), 1);
+        assert_eq!(bus.publish(UserDeleted(1)), 1);
+        assert_eq!(bus.publish(UserCreated(2)), 1);
+        assert_eq!(bus.publish(42u32), 0);
+
+        drop(bus);
+        assert_eq!(created.drain(), vec![UserCreated(1), UserCreated(2)]);
+        assert_eq!(deleted.drain(), vec![UserDeleted(1)]);
+    }
+
+    #[test]
+    fn test_every_subscriber_gets_a_copy() {
+        let bus = TypedBus::new();
+        let tasks: Vec<_> = (0..3)
+            .map(|_| spawn_task(|receiver| receiver.iter().sum::<u32>()))
+            .collect();
+        for task in &tasks {
+            bus.subscribe::<u32, _>(task.sender().map(|val: u32| val * 10));
+        }
+
+        assert_eq!(bus.publish(1u32), 3);
+        assert_eq!(bus.subscriber_count::<u32>(), 3);
+
+        drop(bus);
+        for task in tasks {
+            assert_eq!(task.drain(), 10);
+        }
+    }
+
+    #[test]
+    fn test_gone_subscribers_are_dropped() {
+        let bus = TypedBus::new();
+        let once =
+            spawn_task(|receiver: std::sync::mpsc::Receiver<UserCreated>| receiver.recv().unwrap());
+        bus.subscribe(once.sender());
+
+        assert_eq!(bus.publish(UserCreated(1)), 1);
+        assert_eq!(once.join(), UserCreated(1));
+
+        assert_eq!(bus.publish(UserCreated(2)), 0);
+        assert_eq!(bus.subscriber_count::<UserCreated>(), 0);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(start_paused = true)]
+    async fn test_async_batched_subscribers_receive_vecs() {
+        let bus = TypedBus::new();
+        let task = crate::spawn_async_task(|mut receiver| async move {
+            let mut batches = Vec::new();
+            while let Some(batch) = receiver.recv().await {
+                batches.push(batch);
+            }
+            batches
+        });
+        bus.subscribe_batched::<u32, _>(task.sender(), 3, Duration::from_millis(20));
+
+        for i in 1..=7u32 {
+            bus.publish(i);
+        }
+        // let the batcher take the first values, run out the window of 7
+        // and let the batcher flush it before 8 arrives
+        tokio::task::yield_now().await;
+        tokio::time::advance(Duration::from_millis(50)).await;
+        tokio::task::yield_now().await;
+        bus.publish(8u32);
+
+        drop(bus);
+        assert_eq!(
+            task.drain().await,
+            vec![vec![1, 2, 3], vec![4, 5, 6], vec![7], vec![8]]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Fault injection for testing how tasks cope with unreliable delivery.
+//!
+//! A [`Chaos`] configuration wraps any sender into a [`Recipient`] which
+//! drops, duplicates, reorders or (with tokio) delays messages with the
+//! configured probabilities. Decisions come from a seeded generator, so a
+//! failing run can be reproduced with the same seed.
+//!
+//! Faults are scoped by choosing which senders to wrap: tasks have no names
+//! to match rules against, and there are no remote connections in this
+//! crate. To hit one link only, wrap the sender handed to that peer.
+
+use std::sync::Mutex;
+#[cfg(feature = "tokio")]
+use std::time::Duration;
+
+use crate::{Disconnected, Recipient};
+
+#[derive(Debug, Clone)]
+pub struct Chaos {
+    seed: u64,
+    drop: f64,
+    duplicate: f64,
+    reorder: f64,
+    #[cfg(feature = "tokio")]
+    delay: Option<(f64, Duration)>,
+}
+
+impl Chaos {
+    pub fn new(seed: u64) -> Self {
+        Chaos {
+            seed,
+            drop: 0.0,
+            duplicate: 0.0,
+            reorder: 0.0,
+            #[cfg(feature = "tokio")]
+            delay: None,
+        }
+    }
+
+    pub fn drop(mut self, probability: f64) -> Self {
+        self.drop = probability;
+        self
+    }
+
+    pub fn duplicate(mut self, probability: f64) -> Self {
+        self.duplicate = probability;
+        self
+    }
+
+    /// Holds a message back until the next one was delivered. A message
+    /// still held when the recipient is dropped is delivered then.
+    pub fn reorder(mut self, probability: f64) -> Self {
+        self.reorder = probability;
+        self
+    }
+
+    /// Delivers a message after `delay` from a spawned tokio task instead
+    /// of right away. Sending through the wrapped recipient then needs a
+    /// tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn delay(mut self, probability: f64, delay: Duration) -> Self {
+        self.delay = Some((probability, delay));
+        self
+    }
+
+    pub fn wrap<M, R>(&self, target: R) -> Recipient<M>
+    where
+        M: Clone + Send + 'static,
+        R: Into<Recipient<M>>,
+    {
+        let config = self.clone();
+        let state = Mutex::new(State {
+            rng: Rng::new(self.seed),
+            held: None,
+            target: target.into(),
+        });
+
+        Recipient::new(move |message| state.lock().unwrap().deliver(&config, message))
+    }
+}
+
+struct State<M> {
+    rng: Rng,
+    held: Option<M>,
+    target: Recipient<M>,
+}
+
+impl<M: Clone + Send + 'static> State<M> {
+    fn deliver(&mut self, config: &Chaos, message: M) -> Result<(), Disconnected> {
+        if self.rng.hit(config.drop) {
+            return Ok(());
+        }
+        if self.rng.hit(config.duplicate) {
+            self.forward(config, message.clone())?;
+        }
+        if self.held.is_none() && self.rng.hit(config.reorder) {
+            self.held = Some(message);
+            return Ok(());
+        }
+
+        self.forward(config, message)?;
+        match self.held.take() {
+            Some(held) => self.forward(config, held),
+            None => Ok(()),
+        }
+    }
+
+    #[cfg_attr(not(feature = "tokio"), allow(unused_variables))]
+    fn forward(&mut self, config: &Chaos, message: M) -> Result<(), Disconnected> {
+        #[cfg(feature = "tokio")]
+        if let Some((probability, delay)) = config.delay
+            && self.rng.hit(probability)
+        {
+            let target = self.target.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(delay).await;
+                // a delayed message may outlive its task, like a late packet
+                let _ = target.try_send(message);
+            });
+            return Ok(());
+        }
+
+        self.target.try_send(message)
+    }
+}
+
+impl<M> Drop for State<M> {
+    fn drop(&mut self) {
+        if let Some(held) = self.held.take() {
+            let _ = self.target.try_send(held);
+        }
+    }
+}
+
+/// xorshift64*, good enough to pick faults.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.max(1))
+    }
+
+    fn hit(&mut self, probability: f64) -> bool {
+        if probability <= 0.0 {
+            return false;
+        }
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let sample =
+            (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64;
+        sample < probability
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_task;
+
+    fn collect(chaos: Chaos, messages: impl IntoIterator<Item = u32>) -> Vec<u32> {
+        let task = spawn_task(|receiver| receiver.iter().collect::<Vec<_>>());
+        let recipient = chaos.wrap(task.sender());
+        for message in messages {
+            recipient.send(message);
+        }
+        drop(recipient);
+        task.drain()
+    }
+
+    #[test]
+    fn test_without_faults_everything_arrives_in_order() {
+        assert_eq!(collect(Chaos::new(7), 1..=5), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_certain_faults() {
+        assert!(collect(Chaos::new(7).drop(1.0), 1..=5).is_empty());
+        assert_eq!(
+            collect(Chaos::new(7).duplicate(1.0), 1..=2),
+            vec![1, 1, 2, 2]
+        );
+        assert_eq!(
+            collect(Chaos::new(7).reorder(1.0), 1..=5),
+            vec![2, 1, 4, 3, 5]
+        );
+    }
+
+    #[test]
+    fn test_faults_are_reproducible_per_seed() {
+        let first = collect(Chaos::new(42).drop(0.5), 0..1000);
+        let second = collect(Chaos::new(42).drop(0.5), 0..1000);
+        assert_eq!(first, second);
+        assert!((350..650).contains(&first.len()));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(start_paused = true)]
+    async fn test_async_delayed_messages_arrive_late() {
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let recipient = Chaos::new(7)
+            .delay(1.0, Duration::from_millis(20))
+            .wrap(Recipient::new(move |message: u32| {
+                sender.send(message).map_err(|_| Disconnected)
+            }));
+
+        recipient.send(1);
+        drop(recipient);
+        tokio::task::yield_now().await;
+        tokio::time::advance(Duration::from_millis(19)).await;
+        tokio::task::yield_now().await;
+        assert!(receiver.try_recv().is_err());
+
+        tokio::time::advance(Duration::from_millis(1)).await;
+        assert_eq!(receiver.recv().await, Some(1));
+        assert_eq!(receiver.recv().await, None);
+    }
+}
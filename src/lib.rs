@@ -1,6 +1,23 @@
 mod std_impl;
 pub use self::std_impl::*;
 
+mod blackboard;
+pub use self::blackboard::*;
+
+mod bus;
+pub use self::bus::*;
+
+#[cfg(feature = "chaos")]
+pub mod chaos;
+
+pub mod plugins;
+
+mod recipient;
+pub use self::recipient::*;
+
+mod watchdog;
+pub use self::watchdog::*;
+
 #[cfg(feature = "tokio")]
 pub use tokio;
 #[cfg(feature = "tokio")]
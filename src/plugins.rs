@@ -0,0 +1,369 @@
+use std::{
+    any::{Any, TypeId, type_name},
+    collections::BTreeMap,
+    fmt,
+    sync::Mutex,
+};
+
+#[cfg(feature = "tokio")]
+use crate::AsyncTaskSender;
+use crate::{Recipient, TaskSender};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginError {
+    AlreadyRegistered(String),
+    NotFound(String),
+    TypeMismatch {
+        name: String,
+        /// What is already registered or declared under the name.
+        expected: &'static str,
+        /// What the caller offered or asked for.
+        found: &'static str,
+    },
+}
+
+impl fmt::Display for PluginError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginError::AlreadyRegistered(name) => {
+                write!(f, "plugin `{name}` is already registered")
+            }
+            PluginError::NotFound(name) => write!(f, "no plugin registered as `{name}`"),
+            PluginError::TypeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(f, "plugin `{name}` expects `{expected}`, but got `{found}`"),
+        }
+    }
+}
+
+impl std::error::Error for PluginError {}
+
+/// A registered plugin as seen by the host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginInfo {
+    pub name: String,
+    pub message_type: &'static str,
+}
+
+struct Contract {
+    message: TypeId,
+    message_name: &'static str,
+}
+
+struct Plugin {
+    contract: Contract,
+    sender_name: &'static str,
+    sender: Box<dyn Any + Send>,
+    /// A `Recipient<M>`, whatever kind of sender was registered.
+    recipient: Box<dyn Any + Send>,
+}
+
+#[derive(Default)]
+struct Entries {
+    contracts: BTreeMap<String, Contract>,
+    plugins: BTreeMap<String, Plugin>,
+}
+
+/// Plugins register a sender into their task under a name, the host looks
+/// them up by name and message type. Every plugin can be looked up as a
+/// [`Recipient`], whichever kind of sender it registered.
+///
+/// The host can declare the message type it expects for a name up front
+/// with [`Registry::declare`]; registering a plugin with a different
+/// message type under that name then fails.
+#[derive(Default)]
+pub struct Registry {
+    entries: Mutex<Entries>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn declare<M: 'static>(&self, name: impl Into<String>) -> Result<(), PluginError> {
+        let name = name.into();
+        let mut entries = self.entries.lock().unwrap();
+
+        if let Some(plugin) = entries.plugins.get(&name) {
+            check_contract::<M>(&name, &plugin.contract)?;
+        }
+        entries.contracts.insert(name, Contract::of::<M>());
+        Ok(())
+    }
+
+    pub fn register<M: Send + 'static>(
+        &self,
+        name: impl Into<String>,
+        sender: TaskSender<M>,
+    ) -> Result<(), PluginError> {
+        self.insert::<M, _>(name.into(), sender)
+    }
+
+    #[cfg(feature = "tokio")]
+    pub fn register_async<M: Send + 'static>(
+        &self,
+        name: impl Into<String>,
+        sender: AsyncTaskSender<M>,
+    ) -> Result<(), PluginError> {
+        self.insert::<M, _>(name.into(), sender)
+    }
+
+    pub fn register_recipient<M: Send + 'static>(
+        &self,
+        name: impl Into<String>,
+        recipient: Recipient<M>,
+    ) -> Result<(), PluginError> {
+        self.insert::<M, _>(name.into(), recipient)
+    }
+
+    pub fn unregister(&self, name: &str) -> bool {
+        self.entries.lock().unwrap().plugins.remove(name).is_some()
+    }
+
+    pub fn plugins(&self) -> Vec<PluginInfo> {
+        self.entries
+            .lock()
+            .unwrap()
+            .plugins
+            .iter()
+            .map(|(name, plugin)| PluginInfo {
+                name: name.clone(),
+                message_type: plugin.contract.message_name,
+            })
+            .collect()
+    }
+
+    pub fn sender<M: Send + 'static>(&self, name: &str) -> Result<TaskSender<M>, PluginError> {
+        self.lookup::<TaskSender<M>>(name)
+    }
+
+    #[cfg(feature = "tokio")]
+    pub fn async_sender<M: Send + 'static>(
+        &self,
+        name: &str,
+    ) -> Result<AsyncTaskSender<M>, PluginError> {
+        self.lookup::<AsyncTaskSender<M>>(name)
+    }
+
+    pub fn recipient<M: Send + 'static>(&self, name: &str) -> Result<Recipient<M>, PluginError> {
+        let entries = self.entries.lock().unwrap();
+        let plugin = entries
+            .plugins
+            .get(name)
+            .ok_or_else(|| PluginError::NotFound(name.to_string()))?;
+
+        check_contract::<M>(name, &plugin.contract)?;
+        Ok(plugin
+            .recipient
+            .downcast_ref::<Recipient<M>>()
+            .expect("recipient matches the contract")
+            .clone())
+    }
+
+    fn insert<M: Send + 'static, S>(&self, name: String, sender: S) -> Result<(), PluginError>
+    where
+        S: Into<Recipient<M>> + Clone + Send + 'static,
+    {
+        let mut entries = self.entries.lock().unwrap();
+
+        if entries.plugins.contains_key(&name) {
+            return Err(PluginError::AlreadyRegistered(name));
+        }
+        if let Some(contract) = entries.contracts.get(&name)
+            && contract.message != TypeId::of::<M>()
+        {
+            return Err(PluginError::TypeMismatch {
+                name,
+                expected: contract.message_name,
+                found: type_name::<M>(),
+            });
+        }
+
+        entries.plugins.insert(
+            name,
+            Plugin {
+                contract: Contract::of::<M>(),
+                sender_name: type_name::<S>(),
+                recipient: Box::new(sender.clone().into()),
+                sender: Box::new(sender),
+            },
+        );
+        Ok(())
+    }
+
+    fn lookup<S: Clone + 'static>(&self, name: &str) -> Result<S, PluginError> {
+        let entries = self.entries.lock().unwrap();
+        let plugin = entries
+            .plugins
+            .get(name)
+            .ok_or_else(|| PluginError::NotFound(name.to_string()))?;
+
+        plugin
+            .sender
+            .downcast_ref::<S>()
+            .cloned()
+            .ok_or_else(|| PluginError::TypeMismatch {
+                name: name.to_string(),
+                expected: plugin.sender_name,
+                found: type_name::<S>(),
+            })
+    }
+}
+
+impl Contract {
+    fn of<M: 'static>() -> Self {
+        Contract {
+            message: TypeId::of::<M>(),
+            message_name: type_name::<M>(),
+        }
+    }
+}
+
+fn check_contract<M: 'static>(name: &str, registered: &Contract) -> Result<(), PluginError> {
+    if registered.message == TypeId::of::<M>() {
+        Ok(())
+    } else {
+        Err(PluginError::TypeMismatch {
+            name: name.to_string(),
+            expected: registered.message_name,
+            found: type_name::<M>(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_task;
+
+    #[derive(Clone)]
+    enum Command {
+        Add(u32),
+    }
+
+    #[test]
+    fn test_registered_plugins_are_reachable_by_name() {
+        let registry = Registry::new();
+        let task = spawn_task(|receiver| receiver.iter().map(|Command::Add(val)| val).sum::<u32>());
+        registry.register("adder", task.sender()).unwrap();
+
+        assert_eq!(
+            registry.plugins(),
+            vec![PluginInfo {
+                name: "adder".to_string(),
+                message_type: type_name::<Command>(),
+            }]
+        );
+
+        let adder = registry.sender::<Command>("adder").unwrap();
+        adder.send(Command::Add(1));
+        adder.send(Command::Add(2));
+        drop(adder);
+        assert!(registry.unregister("adder"));
+
+        assert_eq!(task.drain(), 3);
+    }
+
+    #[test]
+    fn test_declared_contracts_reject_wrong_message_types() {
+        let registry = Registry::new();
+        registry.declare::<Command>("adder").unwrap();
+
+        let wrong = spawn_task(|receiver| receiver.iter().count());
+        let error = registry
+            .register::<String>("adder", wrong.sender())
+            .unwrap_err();
+        assert!(matches!(error, PluginError::TypeMismatch { .. }));
+        assert!(registry.plugins().is_empty());
+        wrong.drain();
+    }
+
+    #[test]
+    fn test_lookup_errors() {
+        let registry = Registry::new();
+        let task = spawn_task(|receiver| receiver.iter().count());
+        registry.register::<u32>("counter", task.sender()).unwrap();
+
+        assert_eq!(
+            registry.register::<u32>("counter", task.sender()),
+            Err(PluginError::AlreadyRegistered("counter".to_string()))
+        );
+        assert_eq!(
+            registry
+                .sender::<String>("counter")
+                .err()
+                .unwrap()
+                .to_string(),
+            format!(
+                "plugin `counter` expects `{}`, but got `{}`",
+                type_name::<TaskSender<u32>>(),
+                type_name::<TaskSender<String>>()
+            )
+        );
+        assert!(matches!(
+            registry.sender::<u32>("missing"),
+            Err(PluginError::NotFound(_))
+        ));
+        assert!(matches!(
+            registry.declare::<String>("counter"),
+            Err(PluginError::TypeMismatch { .. })
+        ));
+
+        registry.unregister("counter");
+        task.drain();
+    }
+
+    #[test]
+    fn test_plugins_are_reachable_as_recipients() {
+        let registry = Registry::new();
+        let task = spawn_task(|receiver| receiver.iter().sum::<u32>());
+        registry.register("sender", task.sender()).unwrap();
+        registry
+            .register_recipient("recipient", task.sender().map(|val: u32| val * 10))
+            .unwrap();
+
+        registry.recipient::<u32>("sender").unwrap().send(1);
+        registry.recipient::<u32>("recipient").unwrap().send(2);
+        assert!(matches!(
+            registry.recipient::<String>("recipient"),
+            Err(PluginError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            registry.sender::<u32>("recipient"),
+            Err(PluginError::TypeMismatch { .. })
+        ));
+
+        registry.unregister("sender");
+        registry.unregister("recipient");
+        assert_eq!(task.drain(), 21);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_plugins_are_not_std_senders() {
+        let registry = Registry::new();
+        let task = crate::spawn_async_task(|mut receiver| async move {
+            let mut total = 0;
+            while let Some(val) = receiver.recv().await {
+                total += val;
+            }
+            total
+        });
+        registry
+            .register_async::<u32>("async", task.sender())
+            .unwrap();
+
+        assert!(matches!(
+            registry.sender::<u32>("async"),
+            Err(PluginError::TypeMismatch { .. })
+        ));
+        registry.async_sender::<u32>("async").unwrap().send(5).await;
+        registry.recipient::<u32>("async").unwrap().send(1);
+
+        registry.unregister("async");
+        assert_eq!(task.drain().await, 6);
+    }
+}
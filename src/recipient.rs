@@ -0,0 +1,261 @@
+use std::{fmt, sync::Arc};
+
+#[cfg(feature = "tokio")]
+use crate::AsyncTaskSender;
+use crate::TaskSender;
+
+/// Returned by [`Recipient::try_send`] when the task behind the recipient is
+/// gone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Disconnected;
+
+impl fmt::Display for Disconnected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "recipient is disconnected")
+    }
+}
+
+impl std::error::Error for Disconnected {}
+
+/// A type-erased sender of `M`, usually built from a [`TaskSender`] or an
+/// [`AsyncTaskSender`] via `map`, `filter` or [`Recipient::route`].
+///
+/// The adapters run on the sending side, so no intermediate task is needed
+/// to convert or drop messages.
+pub struct Recipient<M> {
+    deliver: Arc<dyn Fn(M) -> Result<(), Disconnected> + Send + Sync>,
+}
+
+impl<M> Clone for Recipient<M> {
+    fn clone(&self) -> Self {
+        Recipient {
+            deliver: self.deliver.clone(),
+        }
+    }
+}
+
+impl<M> Recipient<M> {
+    pub fn send(&self, message: M) {
+        self.try_send(message)
+            .unwrap_or_else(|_| panic!("task mailbox is closed"))
+    }
+
+    /// Messages dropped by a `filter` count as delivered.
+    pub fn try_send(&self, message: M) -> Result<(), Disconnected> {
+        (self.deliver)(message)
+    }
+}
+
+impl<M: 'static> Recipient<M> {
+    pub fn new<F>(deliver: F) -> Self
+    where
+        F: Fn(M) -> Result<(), Disconnected> + Send + Sync + 'static,
+    {
+        Recipient {
+            deliver: Arc::new(deliver),
+        }
+    }
+
+    /// Sends to whichever recipient `pick` returns for the message.
+    pub fn route<F, R>(pick: F) -> Self
+    where
+        F: Fn(&M) -> R + Send + Sync + 'static,
+        R: Into<Recipient<M>>,
+    {
+        Recipient::new(move |message| pick(&message).into().try_send(message))
+    }
+
+    /// Converts messages of type `N` with `func` before sending them here.
+    pub fn map<N: 'static, F>(self, func: F) -> Recipient<N>
+    where
+        F: Fn(N) -> M + Send + Sync + 'static,
+    {
+        Recipient::new(move |message| self.try_send(func(message)))
+    }
+
+    /// Accepts any message type that converts into `M`, e.g. one variant's
+    /// payload of a wrapper enum.
+    pub fn accepting<N: Into<M> + 'static>(self) -> Recipient<N> {
+        self.map(Into::into)
+    }
+
+    /// Only sends the messages for which `predicate` holds.
+    pub fn filter<F>(self, predicate: F) -> Recipient<M>
+    where
+        F: Fn(&M) -> bool + Send + Sync + 'static,
+    {
+        Recipient::new(move |message| {
+            if predicate(&message) {
+                self.try_send(message)
+            } else {
+                Ok(())
+            }
+        })
+    }
+}
+
+impl<M: Send + 'static> From<TaskSender<M>> for Recipient<M> {
+    fn from(sender: TaskSender<M>) -> Self {
+        Recipient::new(move |message| sender.try_send(message).map_err(|_| Disconnected))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<M: Send + 'static> From<AsyncTaskSender<M>> for Recipient<M> {
+    fn from(sender: AsyncTaskSender<M>) -> Self {
+        Recipient::new(move |message| sender.try_send(message).map_err(|_| Disconnected))
+    }
+}
+
+impl<M: Send + 'static> TaskSender<M> {
+    pub fn map<N: 'static, F>(self, func: F) -> Recipient<N>
+    where
+        F: Fn(N) -> M + Send + Sync + 'static,
+    {
+        Recipient::from(self).map(func)
+    }
+
+    pub fn accepting<N: Into<M> + 'static>(self) -> Recipient<N> {
+        Recipient::from(self).accepting()
+    }
+
+    pub fn filter<F>(self, predicate: F) -> Recipient<M>
+    where
+        F: Fn(&M) -> bool + Send + Sync + 'static,
+    {
+        Recipient::from(self).filter(predicate)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<M: Send + 'static> AsyncTaskSender<M> {
+    pub fn map<N: 'static, F>(self, func: F) -> Recipient<N>
+    where
+        F: Fn(N) -> M + Send + Sync + 'static,
+    {
+        Recipient::from(self).map(func)
+    }
+
+    pub fn accepting<N: Into<M> + 'static>(self) -> Recipient<N> {
+        Recipient::from(self).accepting()
+    }
+
+    pub fn filter<F>(self, predicate: F) -> Recipient<M>
+    where
+        F: Fn(&M) -> bool + Send + Sync + 'static,
+    {
+        Recipient::from(self).filter(predicate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_task;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Event {
+        Reading(u32),
+    }
+
+    #[test]
+    fn test_map_and_filter_adapt_messages() {
+        let task = spawn_task(|receiver| receiver.iter().collect::<Vec<Event>>());
+        let readings = task
+            .sender()
+            .map(Event::Reading)
+            .filter(|reading: &u32| *reading > 10);
+
+        for reading in [5, 20, 8, 30] {
+            readings.send(reading);
+        }
+        drop(readings);
+
+        assert_eq!(task.drain(), vec![Event::Reading(20), Event::Reading(30)]);
+    }
+
+    #[test]
+    fn test_route_picks_a_recipient_per_message() {
+        let even = spawn_task(|receiver| receiver.iter().collect::<Vec<u32>>());
+        let odd = spawn_task(|receiver| receiver.iter().collect::<Vec<u32>>());
+
+        let senders = [even.sender(), odd.sender()];
+        let numbers = Recipient::route(move |number: &u32| senders[*number as usize % 2].clone());
+        for number in 1..=6 {
+            numbers.send(number);
+        }
+        drop(numbers);
+
+        assert_eq!(even.drain(), vec![2, 4, 6]);
+        assert_eq!(odd.drain(), vec![1, 3, 5]);
+    }
+
+    #[test]
+    fn test_try_send_reports_disconnected_recipients() {
+        let task = spawn_task(|receiver| receiver.recv().unwrap());
+        let recipient = Recipient::from(task.sender()).map(|val: u8| u32::from(val));
+
+        recipient.send(1);
+        assert_eq!(task.join(), 1);
+        assert_eq!(recipient.try_send(2), Err(Disconnected));
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Tick(u32);
+
+    #[derive(Debug, PartialEq)]
+    enum Input {
+        Tick(Tick),
+        Event(Event),
+    }
+
+    impl From<Tick> for Input {
+        fn from(tick: Tick) -> Self {
+            Input::Tick(tick)
+        }
+    }
+
+    impl From<Event> for Input {
+        fn from(event: Event) -> Self {
+            Input::Event(event)
+        }
+    }
+
+    #[test]
+    fn test_accepting_converts_into_the_wrapper_message() {
+        let task = spawn_task(|receiver| receiver.iter().collect::<Vec<Input>>());
+        let ticks = task.sender().accepting::<Tick>();
+        let events = task.sender().accepting::<Event>();
+
+        ticks.send(Tick(1));
+        events.send(Event::Reading(2));
+        drop((ticks, events));
+
+        assert_eq!(
+            task.drain(),
+            vec![Input::Tick(Tick(1)), Input::Event(Event::Reading(2))]
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_senders_can_be_adapted() {
+        let task = crate::spawn_async_task(|mut receiver| async move {
+            let mut received = Vec::new();
+            while let Some(event) = receiver.recv().await {
+                received.push(event);
+            }
+            received
+        });
+        let readings = task.sender().map(Event::Reading);
+
+        readings.send(1);
+        readings.send(2);
+        drop(readings);
+
+        assert_eq!(
+            task.drain().await,
+            vec![Event::Reading(1), Event::Reading(2)]
+        );
+    }
+}
@@ -1,8 +1,35 @@
 use std::{
-    sync::mpsc::{Receiver, Sender, channel},
+    sync::mpsc::{Receiver, SendError, Sender, channel},
     thread::JoinHandle,
 };
 
+mod duplex;
+mod dyn_actor;
+mod error;
+mod hooked;
+mod idle;
+mod keyed;
+mod latch;
+mod lazy;
+mod priority;
+mod reducer;
+mod reply;
+mod stash;
+mod timed;
+pub use self::duplex::*;
+pub use self::dyn_actor::*;
+pub use self::error::*;
+pub use self::hooked::*;
+pub use self::idle::*;
+pub use self::keyed::*;
+pub use self::latch::*;
+pub use self::lazy::*;
+pub use self::priority::*;
+pub use self::reducer::*;
+pub use self::reply::*;
+pub use self::stash::*;
+pub use self::timed::*;
+
 #[derive(Clone)]
 struct Mailbox<T>(Sender<T>);
 
@@ -24,6 +51,59 @@ where
     }
 }
 
+impl<T, R> Task<T, R> {
+    /// Returns a cloneable handle for sending into this task's mailbox.
+    pub fn sender(&self) -> TaskSender<T> {
+        TaskSender(self.mailbox.0.clone())
+    }
+
+    /// Drops the task's own sender and waits for the task to handle the
+    /// remaining messages and return. The mailbox only closes once every
+    /// [`TaskSender`] clone is gone as well; until then this blocks.
+    pub fn drain(self) -> R {
+        drop(self.mailbox);
+        self.handle.join().unwrap()
+    }
+}
+
+/// A cloneable handle into a task's mailbox.
+///
+/// Messages sent through one sender (or from one thread) are received in
+/// the order they were sent. Messages of different senders interleave in
+/// no particular order.
+pub struct TaskSender<T>(Sender<T>);
+
+impl<T> Clone for TaskSender<T> {
+    fn clone(&self) -> Self {
+        TaskSender(self.0.clone())
+    }
+}
+
+impl<T> TaskSender<T> {
+    pub fn send(&self, payload: T) {
+        self.0
+            .send(payload)
+            .unwrap_or_else(|_| panic!("task mailbox is closed"))
+    }
+
+    /// Like [`TaskSender::send`], but hands the payload back instead of
+    /// panicking when the task is gone.
+    pub fn try_send(&self, payload: T) -> Result<(), SendError<T>> {
+        self.0.send(payload)
+    }
+}
+
+impl<T, R, E> Task<T, Result<R, E>> {
+    /// Joins a fallible task, telling a returned error apart from a panic.
+    pub fn try_join(self) -> Result<R, TaskError<E>> {
+        match self.handle.join() {
+            Ok(result) => result.map_err(TaskError::Failed),
+            Err(payload) => Err(TaskError::Panicked(payload)),
+        }
+    }
+}
+
+#[cfg(feature = "macros")]
 #[macro_export]
 macro_rules! proc {
     ($($content:tt)*) => {
@@ -53,6 +133,23 @@ where
     }
 }
 
+/// Spawns a task that runs `init` before `func`. Messages sent in the
+/// meantime wait in the mailbox; if `init` fails, the task returns its error
+/// without running `func`.
+pub fn spawn_task_with_init<M, S, E, R, Init, Func>(init: Init, func: Func) -> Task<M, Result<R, E>>
+where
+    M: Send + 'static,
+    R: Send + 'static,
+    E: Send + 'static,
+    Init: FnOnce() -> Result<S, E> + Send + 'static,
+    Func: FnOnce(S, Receiver<M>) -> R + Send + 'static,
+{
+    spawn_task(move |receiver| {
+        let state = init()?;
+        Ok(func(state, receiver))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -157,6 +254,143 @@ mod tests {
         assert_eq!(result, 42);
     }
 
+    #[test]
+    fn test_drain_finishes_pending_messages() {
+        let task = spawn_task(|receiver| {
+            let mut total = 0;
+            for val in receiver {
+                std::thread::sleep(std::time::Duration::from_millis(1));
+                total += val;
+            }
+            total
+        });
+
+        for i in 1..=10 {
+            task.send(i);
+        }
+
+        let result = task.drain();
+        assert_eq!(result, 55);
+    }
+
+    #[test]
+    fn test_try_join_distinguishes_errors_from_panics() {
+        let ok = spawn_task(|receiver| -> Result<u32, String> { Ok(receiver.recv().unwrap()) });
+        ok.send(1);
+        assert_eq!(ok.try_join().unwrap(), 1);
+
+        let failed = spawn_task(|receiver| -> Result<u32, String> {
+            Err(format!("bad input {}", receiver.recv().unwrap()))
+        });
+        failed.send(2);
+        let error = failed.try_join().unwrap_err();
+        assert_eq!(error.to_string(), "task failed: bad input 2");
+        assert_eq!(error.into_failure(), Some("bad input 2".to_string()));
+
+        let panicked = spawn_task(|receiver| -> Result<u32, String> {
+            let val: u32 = receiver.recv().unwrap();
+            panic!("boom {val}")
+        });
+        panicked.send(3);
+        assert!(panicked.try_join().unwrap_err().is_panic());
+    }
+
+    #[test]
+    fn test_try_join_without_clone_messages() {
+        struct Job(u32);
+
+        let task = spawn_task(|receiver: Receiver<Job>| -> Result<u32, String> {
+            receiver
+                .recv()
+                .map(|job| job.0)
+                .map_err(|_| "no job".to_string())
+        });
+        task.sender().send(Job(7));
+        assert_eq!(task.try_join().unwrap(), 7);
+    }
+
+    #[test]
+    fn test_senders_share_the_mailbox() {
+        let task = spawn_task(|receiver| receiver.iter().sum::<u32>());
+
+        let first = task.sender();
+        let second = first.clone();
+        let producer = std::thread::spawn(move || {
+            for i in 1..=5 {
+                second.send(i);
+            }
+        });
+        first.send(100);
+        producer.join().unwrap();
+        drop(first);
+
+        assert_eq!(task.drain(), 115);
+    }
+
+    #[test]
+    fn test_messages_keep_per_sender_order() {
+        let task = spawn_task(|receiver| receiver.iter().collect::<Vec<(u32, u32)>>());
+
+        let producers: Vec<_> = (0..4)
+            .map(|producer| {
+                let sender = task.sender();
+                std::thread::spawn(move || {
+                    for seq in 0..250 {
+                        sender.send((producer, seq));
+                    }
+                })
+            })
+            .collect();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        let received = task.drain();
+        assert_eq!(received.len(), 1000);
+        for producer in 0..4 {
+            let sequence: Vec<u32> = received
+                .iter()
+                .filter(|(from, _)| *from == producer)
+                .map(|(_, seq)| *seq)
+                .collect();
+            assert_eq!(sequence, (0..250).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_messages_sent_during_init_are_delivered_afterwards() {
+        let (ready, is_ready) = channel::<()>();
+        let task = spawn_task_with_init(
+            move || {
+                is_ready.recv().unwrap();
+                Ok::<_, String>(100)
+            },
+            |offset, receiver| {
+                receiver
+                    .iter()
+                    .map(|val: u32| val + offset)
+                    .collect::<Vec<_>>()
+            },
+        );
+
+        task.send(1);
+        task.send(2);
+        ready.send(()).unwrap();
+
+        assert_eq!(task.drain(), Ok(vec![101, 102]));
+    }
+
+    #[test]
+    fn test_failed_init_skips_the_task_body() {
+        let task = spawn_task_with_init(
+            || Err::<u32, _>("no database".to_string()),
+            |_, _receiver: Receiver<u32>| unreachable!(),
+        );
+
+        let error = task.try_join().unwrap_err();
+        assert_eq!(error.into_failure(), Some("no database".to_string()));
+    }
+
     #[test]
     fn test_different_number_types() {
         let task = spawn_task(|receiver| {
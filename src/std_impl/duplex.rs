@@ -0,0 +1,116 @@
+use std::sync::mpsc::{Receiver, RecvError, SendError, Sender, TryRecvError, channel};
+
+use super::{Task, TaskSender, spawn_task};
+
+/// The output side of a duplex task.
+pub struct Outbox<T>(Sender<T>);
+
+impl<T> Clone for Outbox<T> {
+    fn clone(&self) -> Self {
+        Outbox(self.0.clone())
+    }
+}
+
+impl<T> Outbox<T> {
+    /// Fails once the parent dropped the output side, which a task may take
+    /// as a reason to stop.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.0.send(value)
+    }
+}
+
+/// A task with a mailbox for its inputs and a stream of outputs, for tasks
+/// that transform rather than just consume messages.
+pub struct DuplexTask<In, Out, R> {
+    task: Task<In, R>,
+    outputs: Receiver<Out>,
+}
+
+impl<In, Out, R> DuplexTask<In, Out, R> {
+    pub fn send(&self, payload: In) {
+        self.task
+            .mailbox
+            .0
+            .send(payload)
+            .unwrap_or_else(|_| panic!("task mailbox is closed"))
+    }
+
+    pub fn sender(&self) -> TaskSender<In> {
+        self.task.sender()
+    }
+
+    /// Blocks until the task emits an output. Fails once the task finished
+    /// and all its outputs were received.
+    pub fn recv_output(&self) -> Result<Out, RecvError> {
+        self.outputs.recv()
+    }
+
+    pub fn try_recv_output(&self) -> Result<Out, TryRecvError> {
+        self.outputs.try_recv()
+    }
+
+    /// Outputs not received yet are dropped.
+    pub fn drain(self) -> R {
+        self.task.drain()
+    }
+
+    pub fn split(self) -> (Task<In, R>, Receiver<Out>) {
+        (self.task, self.outputs)
+    }
+}
+
+pub fn spawn_duplex_task<In, Out, R, Func>(func: Func) -> DuplexTask<In, Out, R>
+where
+    In: Send + 'static,
+    Out: Send + 'static,
+    R: Send + 'static,
+    Func: FnOnce(Receiver<In>, Outbox<Out>) -> R + Send + 'static,
+{
+    let (outbox, outputs) = channel();
+    let task = spawn_task(move |receiver| func(receiver, Outbox(outbox)));
+
+    DuplexTask { task, outputs }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplex_task_streams_outputs() {
+        let task = spawn_duplex_task(|receiver, outbox| {
+            let mut count = 0;
+            for line in receiver.iter() {
+                let line: String = line;
+                for word in line.split_whitespace() {
+                    outbox.send(word.to_uppercase()).unwrap();
+                    count += 1;
+                }
+            }
+            count
+        });
+
+        task.send("hello world".to_string());
+        assert_eq!(task.recv_output(), Ok("HELLO".to_string()));
+        assert_eq!(task.recv_output(), Ok("WORLD".to_string()));
+        assert_eq!(task.try_recv_output(), Err(TryRecvError::Empty));
+
+        task.send("again".to_string());
+        let (task, outputs) = task.split();
+        assert_eq!(task.drain(), 3);
+        assert_eq!(outputs.iter().collect::<Vec<_>>(), vec!["AGAIN"]);
+    }
+
+    #[test]
+    fn test_outbox_reports_gone_parents() {
+        let task = spawn_duplex_task(|receiver: Receiver<()>, outbox| {
+            receiver.recv().unwrap();
+            outbox.send(1).is_err()
+        });
+
+        let (task, outputs) = task.split();
+        drop(outputs);
+        task.sender().send(());
+        assert!(task.drain());
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    sync::mpsc::{Sender, channel},
+    thread::JoinHandle,
+};
+
+/// Message handling logic that can be swapped at runtime, see
+/// [`spawn_dyn_actor`].
+pub trait DynHandler<M>: Send {
+    fn handle(&mut self, message: M);
+}
+
+impl<M, F> DynHandler<M> for F
+where
+    F: FnMut(M) + Send,
+{
+    fn handle(&mut self, message: M) {
+        self(message)
+    }
+}
+
+enum DynMessage<M> {
+    Message(M),
+    SwapHandler(Box<dyn DynHandler<M>>),
+}
+
+/// An actor whose behavior is a boxed [`DynHandler`] that can be replaced
+/// without losing queued messages.
+///
+/// A swap is queued like any other message: everything sent before it is
+/// handled by the old handler, everything after by the new one.
+pub struct DynActor<M> {
+    sender: Sender<DynMessage<M>>,
+    handle: JoinHandle<Box<dyn DynHandler<M>>>,
+}
+
+impl<M> DynActor<M> {
+    pub fn send(&self, payload: M) {
+        self.enqueue(DynMessage::Message(payload))
+    }
+
+    pub fn swap_handler(&self, handler: Box<dyn DynHandler<M>>) {
+        self.enqueue(DynMessage::SwapHandler(handler))
+    }
+
+    /// Closes the mailbox, waits for the queued messages to be handled and
+    /// returns the handler that was active last.
+    pub fn drain(self) -> Box<dyn DynHandler<M>> {
+        drop(self.sender);
+        self.handle.join().unwrap()
+    }
+
+    fn enqueue(&self, message: DynMessage<M>) {
+        self.sender
+            .send(message)
+            .unwrap_or_else(|_| panic!("task mailbox is closed"))
+    }
+}
+
+pub fn spawn_dyn_actor<M>(handler: Box<dyn DynHandler<M>>) -> DynActor<M>
+where
+    M: Send + 'static,
+{
+    let (sender, receiver) = channel::<DynMessage<M>>();
+    let handle = std::thread::spawn(move || {
+        let mut handler = handler;
+        for message in receiver {
+            match message {
+                DynMessage::Message(payload) => handler.handle(payload),
+                DynMessage::SwapHandler(next) => handler = next,
+            }
+        }
+        handler
+    });
+
+    DynActor { sender, handle }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    type Output = Arc<Mutex<Vec<u32>>>;
+
+    fn scaling(factor: u32, output: &Output) -> Box<dyn DynHandler<u32>> {
+        let output = output.clone();
+        Box::new(move |val| output.lock().unwrap().push(val * factor))
+    }
+
+    #[test]
+    fn test_swap_keeps_pending_messages_in_order() {
+        let output = Output::default();
+        let actor = spawn_dyn_actor(scaling(1, &output));
+
+        actor.send(1);
+        actor.send(2);
+        actor.swap_handler(scaling(10, &output));
+        actor.send(3);
+        actor.swap_handler(scaling(100, &output));
+        actor.send(4);
+        actor.drain();
+
+        assert_eq!(*output.lock().unwrap(), vec![1, 2, 30, 400]);
+    }
+
+    struct Summing {
+        total: u32,
+        output: Output,
+    }
+
+    impl DynHandler<u32> for Summing {
+        fn handle(&mut self, message: u32) {
+            self.total += message;
+            self.output.lock().unwrap().push(self.total);
+        }
+    }
+
+    #[test]
+    fn test_drain_returns_last_handler() {
+        let output = Output::default();
+        let actor = spawn_dyn_actor(scaling(1, &output));
+
+        actor.send(1);
+        actor.swap_handler(Box::new(Summing {
+            total: 0,
+            output: output.clone(),
+        }));
+        actor.send(2);
+        actor.send(3);
+
+        let mut last = actor.drain();
+        last.handle(4);
+        assert_eq!(*output.lock().unwrap(), vec![1, 2, 5, 9]);
+    }
+}
@@ -0,0 +1,46 @@
+use std::{any::Any, fmt};
+
+/// Why a task whose body returns `Result<R, E>` did not produce an `R`.
+#[derive(Debug)]
+pub enum TaskError<E> {
+    /// The task body returned `Err`.
+    Failed(E),
+    /// The task body panicked, carrying the panic payload.
+    Panicked(Box<dyn Any + Send>),
+    /// The task was aborted before it finished. Only async tasks can be
+    /// aborted.
+    Aborted,
+}
+
+impl<E> TaskError<E> {
+    pub fn is_failed(&self) -> bool {
+        matches!(self, TaskError::Failed(_))
+    }
+
+    pub fn is_panic(&self) -> bool {
+        matches!(self, TaskError::Panicked(_))
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        matches!(self, TaskError::Aborted)
+    }
+
+    pub fn into_failure(self) -> Option<E> {
+        match self {
+            TaskError::Failed(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for TaskError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaskError::Failed(error) => write!(f, "task failed: {error}"),
+            TaskError::Panicked(_) => write!(f, "task panicked"),
+            TaskError::Aborted => write!(f, "task was aborted"),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for TaskError<E> {}
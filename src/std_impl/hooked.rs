@@ -0,0 +1,115 @@
+use std::sync::mpsc::{Receiver, RecvError, TryRecvError};
+
+/// Wraps a task's receiver and runs `before` on every received message and
+/// `after` once the task is done handling it, i.e. when it asks for the
+/// next message or drops the receiver.
+pub struct HookedReceiver<M, Before, After>
+where
+    After: FnMut(),
+{
+    receiver: Receiver<M>,
+    before: Before,
+    after: After,
+    handling: bool,
+}
+
+impl<M, Before, After> HookedReceiver<M, Before, After>
+where
+    Before: FnMut(&M),
+    After: FnMut(),
+{
+    pub fn new(receiver: Receiver<M>, before: Before, after: After) -> Self {
+        HookedReceiver {
+            receiver,
+            before,
+            after,
+            handling: false,
+        }
+    }
+
+    pub fn recv(&mut self) -> Result<M, RecvError> {
+        self.handled();
+        let payload = self.receiver.recv()?;
+        self.received(&payload);
+        Ok(payload)
+    }
+
+    pub fn try_recv(&mut self) -> Result<M, TryRecvError> {
+        self.handled();
+        let payload = self.receiver.try_recv()?;
+        self.received(&payload);
+        Ok(payload)
+    }
+
+    fn received(&mut self, payload: &M) {
+        (self.before)(payload);
+        self.handling = true;
+    }
+
+    fn handled(&mut self) {
+        if std::mem::take(&mut self.handling) {
+            (self.after)();
+        }
+    }
+}
+
+impl<M, Before, After> Drop for HookedReceiver<M, Before, After>
+where
+    After: FnMut(),
+{
+    fn drop(&mut self) {
+        if self.handling {
+            (self.after)();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_task;
+    use std::{cell::Cell, sync::mpsc::channel};
+
+    thread_local! {
+        static REQUEST_ID: Cell<Option<u32>> = const { Cell::new(None) };
+    }
+
+    #[test]
+    fn test_hooks_run_around_each_message() {
+        let task = spawn_task(|receiver| {
+            let mut receiver = HookedReceiver::new(
+                receiver,
+                |id: &u32| REQUEST_ID.set(Some(*id)),
+                || REQUEST_ID.set(None),
+            );
+            let mut seen = Vec::new();
+            while let Ok(id) = receiver.recv() {
+                seen.push((id, REQUEST_ID.get()));
+            }
+            (seen, REQUEST_ID.get())
+        });
+
+        task.send(1);
+        task.send(2);
+
+        let (seen, after_last) = task.drain();
+        assert_eq!(seen, vec![(1, Some(1)), (2, Some(2))]);
+        assert_eq!(after_last, None);
+    }
+
+    #[test]
+    fn test_after_hook_runs_when_receiver_is_dropped() {
+        let (sender, receiver) = channel();
+        let after = Cell::new(0);
+        let mut receiver =
+            HookedReceiver::new(receiver, |_: &u32| {}, || after.set(after.get() + 1));
+
+        assert!(receiver.try_recv().is_err());
+        sender.send(1).unwrap();
+        receiver.try_recv().unwrap();
+        assert_eq!(after.get(), 0);
+
+        drop(receiver);
+        assert_eq!(after.get(), 1);
+    }
+}
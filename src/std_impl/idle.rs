@@ -0,0 +1,68 @@
+use std::{
+    sync::mpsc::{Receiver, RecvError, RecvTimeoutError},
+    time::Duration,
+};
+
+/// What an [`IdleReceiver`] hands out: a message, or the news that none
+/// arrived for the idle timeout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Received<M> {
+    Message(M),
+    Idle,
+}
+
+/// Wraps a task's receiver and reports [`Received::Idle`] once the mailbox
+/// stayed empty for `idle_timeout`, e.g. to passivate a per-connection
+/// task. Whether to stop or keep waiting is up to the task; waiting again
+/// reports `Idle` again after another timeout.
+pub struct IdleReceiver<M> {
+    receiver: Receiver<M>,
+    idle_timeout: Duration,
+}
+
+impl<M> IdleReceiver<M> {
+    pub fn new(receiver: Receiver<M>, idle_timeout: Duration) -> Self {
+        IdleReceiver {
+            receiver,
+            idle_timeout,
+        }
+    }
+
+    pub fn recv(&mut self) -> Result<Received<M>, RecvError> {
+        match self.receiver.recv_timeout(self.idle_timeout) {
+            Ok(payload) => Ok(Received::Message(payload)),
+            Err(RecvTimeoutError::Timeout) => Ok(Received::Idle),
+            Err(RecvTimeoutError::Disconnected) => Err(RecvError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_task;
+
+    #[test]
+    fn test_idle_receiver_reports_idle_mailboxes() {
+        let task = spawn_task(|receiver| {
+            let mut receiver = IdleReceiver::new(receiver, Duration::from_millis(20));
+            let mut received = Vec::new();
+            while let Ok(event) = receiver.recv() {
+                let idle = event == Received::Idle;
+                received.push(event);
+                if idle {
+                    break;
+                }
+            }
+            received
+        });
+
+        task.send(1);
+        task.send(2);
+
+        assert_eq!(
+            task.join(),
+            vec![Received::Message(1), Received::Message(2), Received::Idle]
+        );
+    }
+}
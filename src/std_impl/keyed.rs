@@ -0,0 +1,222 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::mpsc::{Sender, channel},
+    thread::JoinHandle,
+};
+
+enum Routed<K, M> {
+    Message(K, M),
+    Flush(Sender<()>),
+}
+
+struct Worker<K, M> {
+    sender: Sender<Routed<K, M>>,
+    handle: JoinHandle<()>,
+}
+
+type Factory<K, M> = Box<dyn Fn(usize) -> Box<dyn FnMut(K, M) + Send> + Send>;
+
+/// Routes messages to a fixed set of workers by key.
+///
+/// All messages with the same key are handled in order by the same worker,
+/// while different keys may be processed in parallel. Adding or removing
+/// workers waits for in-flight messages to be handled before keys are
+/// rehashed, so the per-key ordering survives resizing.
+pub struct KeyedRouter<K, M> {
+    workers: Vec<Worker<K, M>>,
+    factory: Factory<K, M>,
+}
+
+impl<K, M> KeyedRouter<K, M>
+where
+    K: Hash + Send + 'static,
+    M: Send + 'static,
+{
+    pub fn new<F, H>(workers: usize, factory: F) -> Self
+    where
+        F: Fn(usize) -> H + Send + 'static,
+        H: FnMut(K, M) + Send + 'static,
+    {
+        assert!(workers > 0, "a keyed router needs at least one worker");
+
+        let mut router = KeyedRouter {
+            workers: Vec::with_capacity(workers),
+            factory: Box::new(move |id| Box::new(factory(id))),
+        };
+        for _ in 0..workers {
+            router.spawn_worker();
+        }
+        router
+    }
+
+    pub fn send(&self, key: K, payload: M) {
+        let index = self.route(&key);
+        self.workers[index]
+            .sender
+            .send(Routed::Message(key, payload))
+            .unwrap()
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn add_worker(&mut self) {
+        self.flush();
+        self.spawn_worker();
+    }
+
+    pub fn remove_worker(&mut self) {
+        assert!(
+            self.workers.len() > 1,
+            "cannot remove the last worker of a keyed router"
+        );
+
+        let worker = self.workers.pop().unwrap();
+        drop(worker.sender);
+        worker.handle.join().unwrap();
+        self.flush();
+    }
+
+    pub fn join(self) {
+        for worker in self.workers {
+            drop(worker.sender);
+            worker.handle.join().unwrap();
+        }
+    }
+
+    fn route(&self, key: &K) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.workers.len() as u64) as usize
+    }
+
+    fn flush(&self) {
+        let acks = self
+            .workers
+            .iter()
+            .map(|worker| {
+                let (ack, done) = channel();
+                worker.sender.send(Routed::Flush(ack)).unwrap();
+                done
+            })
+            .collect::<Vec<_>>();
+
+        for done in acks {
+            done.recv().unwrap();
+        }
+    }
+
+    fn spawn_worker(&mut self) {
+        let mut handler = (self.factory)(self.workers.len());
+        let (sender, receiver) = channel::<Routed<K, M>>();
+        let handle = std::thread::spawn(move || {
+            for routed in receiver {
+                match routed {
+                    Routed::Message(key, payload) => handler(key, payload),
+                    Routed::Flush(ack) => ack.send(()).unwrap(),
+                }
+            }
+        });
+
+        self.workers.push(Worker { sender, handle });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    type Log = Arc<Mutex<Vec<(usize, u32, u32)>>>;
+
+    fn logging_router(workers: usize, log: &Log) -> KeyedRouter<u32, u32> {
+        let log = log.clone();
+        KeyedRouter::new(workers, move |id| {
+            let log = log.clone();
+            move |key, value| log.lock().unwrap().push((id, key, value))
+        })
+    }
+
+    fn assert_in_order_per_key(log: &Log, keys: u32, per_key: u32) {
+        let log = log.lock().unwrap();
+        for key in 0..keys {
+            let values = log
+                .iter()
+                .filter(|(_, k, _)| *k == key)
+                .map(|(_, _, v)| *v)
+                .collect::<Vec<_>>();
+            assert_eq!(values, (0..per_key).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_same_key_is_handled_by_same_worker() {
+        let log = Log::default();
+        let router = logging_router(4, &log);
+
+        for value in 0..10 {
+            for key in 0..8 {
+                router.send(key, value);
+            }
+        }
+        router.join();
+
+        let log = log.lock().unwrap();
+        for key in 0..8 {
+            let mut workers = log
+                .iter()
+                .filter(|(_, k, _)| *k == key)
+                .map(|(id, _, _)| *id)
+                .collect::<Vec<_>>();
+            workers.dedup();
+            assert_eq!(workers.len(), 1);
+        }
+    }
+
+    #[test]
+    fn test_messages_per_key_stay_in_order() {
+        let log = Log::default();
+        let router = logging_router(3, &log);
+
+        for value in 0..50 {
+            for key in 0..5 {
+                router.send(key, value);
+            }
+        }
+        router.join();
+
+        assert_in_order_per_key(&log, 5, 50);
+    }
+
+    #[test]
+    fn test_order_survives_adding_and_removing_workers() {
+        let log = Log::default();
+        let mut router = logging_router(2, &log);
+
+        for value in 0..20 {
+            for key in 0..10 {
+                router.send(key, value);
+            }
+            if value == 5 {
+                router.add_worker();
+                router.add_worker();
+            }
+            if value == 12 {
+                router.remove_worker();
+            }
+        }
+        assert_eq!(router.workers(), 3);
+        router.join();
+
+        assert_in_order_per_key(&log, 10, 20);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_cannot_remove_last_worker() {
+        let mut router = KeyedRouter::<u32, u32>::new(1, |_| |_, _| {});
+        router.remove_worker();
+    }
+}
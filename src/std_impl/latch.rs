@@ -0,0 +1,102 @@
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+/// A countdown that opens once it reached zero, e.g. to hold back serving
+/// traffic until all workers finished their startup.
+///
+/// Each worker gets a clone and calls [`Latch::count_down`] when it is
+/// ready, the main program blocks in [`Latch::wait`]. Counting down an
+/// open latch does nothing.
+#[derive(Clone)]
+pub struct Latch {
+    inner: Arc<(Mutex<usize>, Condvar)>,
+}
+
+impl Latch {
+    pub fn new(count: usize) -> Self {
+        Latch {
+            inner: Arc::new((Mutex::new(count), Condvar::new())),
+        }
+    }
+
+    pub fn count_down(&self) {
+        let (count, opened) = &*self.inner;
+        let mut count = count.lock().unwrap();
+        if *count > 0 {
+            *count -= 1;
+            if *count == 0 {
+                opened.notify_all();
+            }
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        *self.inner.0.lock().unwrap()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.count() == 0
+    }
+
+    pub fn wait(&self) {
+        let (count, opened) = &*self.inner;
+        let _open = opened
+            .wait_while(count.lock().unwrap(), |count| *count > 0)
+            .unwrap();
+    }
+
+    /// Returns whether the latch opened within `timeout`.
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        let (count, opened) = &*self.inner;
+        let (_count, result) = opened
+            .wait_timeout_while(count.lock().unwrap(), timeout, |count| *count > 0)
+            .unwrap();
+        !result.timed_out()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Task, spawn_task};
+    use std::sync::mpsc::Receiver;
+
+    #[test]
+    fn test_latch_opens_after_all_workers_are_ready() {
+        let ready = Latch::new(3);
+
+        let workers: Vec<Task<(), u32>> = (0..3)
+            .map(|id| {
+                let ready = ready.clone();
+                spawn_task(move |receiver: Receiver<()>| {
+                    ready.count_down();
+                    receiver.iter().count();
+                    id
+                })
+            })
+            .collect();
+
+        ready.wait();
+        assert!(ready.is_open());
+
+        for worker in workers {
+            worker.drain();
+        }
+    }
+
+    #[test]
+    fn test_latch_wait_timeout() {
+        let ready = Latch::new(2);
+        ready.count_down();
+
+        assert!(!ready.wait_timeout(Duration::from_millis(10)));
+        assert_eq!(ready.count(), 1);
+
+        ready.count_down();
+        ready.count_down();
+        assert!(ready.wait_timeout(Duration::from_millis(10)));
+        assert_eq!(ready.count(), 0);
+    }
+}
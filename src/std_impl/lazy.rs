@@ -0,0 +1,167 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        mpsc::{Receiver, Sender, channel},
+    },
+    thread::JoinHandle,
+};
+
+type Body<M, R> = Box<dyn FnOnce(Receiver<M>) -> R + Send>;
+
+enum State<M, R> {
+    Pending(Body<M, R>, Receiver<M>),
+    /// The handle is taken out once the task is joined.
+    Running(Option<JoinHandle<R>>),
+}
+
+type Shared<M, R> = Arc<Mutex<State<M, R>>>;
+
+/// A task that is only spawned once it receives its first message (or is
+/// joined).
+pub struct LazyTask<M, R> {
+    sender: LazyTaskSender<M, R>,
+}
+
+impl<M, R> LazyTask<M, R>
+where
+    M: Send + 'static,
+    R: Send + 'static,
+{
+    pub fn send(&self, payload: M) {
+        self.sender.send(payload)
+    }
+
+    /// A handle that starts the task on its first message as well, for
+    /// other threads to send through before the task is running.
+    pub fn sender(&self) -> LazyTaskSender<M, R> {
+        self.sender.clone()
+    }
+
+    pub fn is_started(&self) -> bool {
+        matches!(*self.sender.state.lock().unwrap(), State::Running(_))
+    }
+
+    pub fn join(self) -> R {
+        let state = self.sender.state.clone();
+        start(&state);
+        Self::handle_of(&state).join().unwrap()
+    }
+
+    pub fn drain(self) -> R {
+        let state = self.sender.state.clone();
+        start(&state);
+        drop(self.sender);
+        Self::handle_of(&state).join().unwrap()
+    }
+
+    fn handle_of(state: &Mutex<State<M, R>>) -> JoinHandle<R> {
+        match &mut *state.lock().unwrap() {
+            State::Running(handle) => handle.take(),
+            State::Pending(..) => None,
+        }
+        .expect("lazy task is started and joined once")
+    }
+}
+
+pub struct LazyTaskSender<M, R> {
+    sender: Sender<M>,
+    state: Shared<M, R>,
+}
+
+impl<M, R> Clone for LazyTaskSender<M, R> {
+    fn clone(&self) -> Self {
+        LazyTaskSender {
+            sender: self.sender.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<M, R> LazyTaskSender<M, R>
+where
+    M: Send + 'static,
+    R: Send + 'static,
+{
+    pub fn send(&self, payload: M) {
+        self.sender
+            .send(payload)
+            .unwrap_or_else(|_| panic!("task mailbox is closed"));
+        start(&self.state);
+    }
+}
+
+fn start<M, R>(state: &Mutex<State<M, R>>)
+where
+    M: Send + 'static,
+    R: Send + 'static,
+{
+    let mut state = state.lock().unwrap();
+    *state = match std::mem::replace(&mut *state, State::Running(None)) {
+        State::Pending(func, receiver) => {
+            State::Running(Some(std::thread::spawn(move || func(receiver))))
+        }
+        running => running,
+    };
+}
+
+pub fn spawn_lazy_task<M, R, Func>(func: Func) -> LazyTask<M, R>
+where
+    M: Send + 'static,
+    R: Send + 'static,
+    Func: FnOnce(Receiver<M>) -> R + Send + 'static,
+{
+    let (sender, receiver) = channel::<M>();
+
+    LazyTask {
+        sender: LazyTaskSender {
+            sender,
+            state: Arc::new(Mutex::new(State::Pending(Box::new(func), receiver))),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn test_lazy_task_starts_on_first_message() {
+        let started = Arc::new(AtomicBool::new(false));
+        let flag = started.clone();
+        let task = spawn_lazy_task(move |receiver| {
+            flag.store(true, Ordering::SeqCst);
+            receiver.iter().sum::<u32>()
+        });
+
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert!(!task.is_started());
+        assert!(!started.load(Ordering::SeqCst));
+
+        task.send(1);
+        assert!(task.is_started());
+        task.send(2);
+
+        assert_eq!(task.drain(), 3);
+        assert!(started.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_joining_unstarted_lazy_task_runs_it() {
+        let task = spawn_lazy_task::<u32, _, _>(|_receiver| 42);
+
+        assert!(!task.is_started());
+        assert_eq!(task.join(), 42);
+    }
+
+    #[test]
+    fn test_lazy_task_sender_starts_the_task() {
+        let task = spawn_lazy_task(|receiver| receiver.iter().sum::<u32>());
+        let sender = task.sender();
+
+        std::thread::spawn(move || sender.send(5)).join().unwrap();
+        assert!(task.is_started());
+
+        assert_eq!(task.drain(), 5);
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{Receiver, RecvError, Sender, TryRecvError, channel},
+    thread::JoinHandle,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lane {
+    High,
+    Normal,
+}
+
+/// A task with two mailbox lanes. Messages sent with
+/// [`PriorityTask::send_priority`] are received before any normal message
+/// that is already waiting.
+pub struct PriorityTask<M, R> {
+    sender: Sender<(Lane, M)>,
+    handle: JoinHandle<R>,
+}
+
+impl<M, R> PriorityTask<M, R> {
+    pub fn send(&self, payload: M) {
+        self.enqueue(Lane::Normal, payload)
+    }
+
+    pub fn send_priority(&self, payload: M) {
+        self.enqueue(Lane::High, payload)
+    }
+
+    pub fn join(self) -> R {
+        self.handle.join().unwrap()
+    }
+
+    pub fn drain(self) -> R {
+        drop(self.sender);
+        self.handle.join().unwrap()
+    }
+
+    fn enqueue(&self, lane: Lane, payload: M) {
+        self.sender
+            .send((lane, payload))
+            .unwrap_or_else(|_| panic!("task mailbox is closed"))
+    }
+}
+
+pub struct PriorityReceiver<M> {
+    receiver: Receiver<(Lane, M)>,
+    high: VecDeque<M>,
+    normal: VecDeque<M>,
+    aging: Option<usize>,
+    overtaken: usize,
+}
+
+impl<M> PriorityReceiver<M> {
+    /// Prevents a steady stream of high-priority messages from starving the
+    /// normal lane: once `after` high-priority messages overtook the oldest
+    /// waiting normal message, that message is received next.
+    pub fn set_aging(&mut self, after: usize) {
+        assert!(after > 0, "aging needs at least one overtaking message");
+        self.aging = Some(after);
+    }
+
+    /// Blocks until a message is available, preferring the high lane.
+    pub fn recv(&mut self) -> Result<M, RecvError> {
+        if self.high.is_empty() && self.normal.is_empty() {
+            let (lane, payload) = self.receiver.recv()?;
+            self.push(lane, payload);
+        }
+        self.collect_pending();
+        Ok(self.pop().unwrap())
+    }
+
+    pub fn try_recv(&mut self) -> Result<M, TryRecvError> {
+        let error = self.collect_pending();
+        self.pop().ok_or(error)
+    }
+
+    /// Moves everything waiting in the channel into the lanes and returns
+    /// why the channel had nothing more.
+    fn collect_pending(&mut self) -> TryRecvError {
+        loop {
+            match self.receiver.try_recv() {
+                Ok((lane, payload)) => self.push(lane, payload),
+                Err(error) => return error,
+            }
+        }
+    }
+
+    fn push(&mut self, lane: Lane, payload: M) {
+        match lane {
+            Lane::High => self.high.push_back(payload),
+            Lane::Normal => self.normal.push_back(payload),
+        }
+    }
+
+    fn pop(&mut self) -> Option<M> {
+        if let Some(after) = self.aging
+            && self.overtaken >= after
+            && let Some(payload) = self.normal.pop_front()
+        {
+            self.overtaken = 0;
+            return Some(payload);
+        }
+
+        match self.high.pop_front() {
+            Some(payload) => {
+                if !self.normal.is_empty() {
+                    self.overtaken += 1;
+                }
+                Some(payload)
+            }
+            None => {
+                self.overtaken = 0;
+                self.normal.pop_front()
+            }
+        }
+    }
+}
+
+pub fn spawn_priority_task<M, R, Func>(func: Func) -> PriorityTask<M, R>
+where
+    M: Send + 'static,
+    R: Send + 'static,
+    Func: FnOnce(PriorityReceiver<M>) -> R + Send + 'static,
+{
+    let (sender, receiver) = channel::<(Lane, M)>();
+    let receiver = PriorityReceiver {
+        receiver,
+        high: VecDeque::new(),
+        normal: VecDeque::new(),
+        aging: None,
+        overtaken: 0,
+    };
+    let handle = std::thread::spawn(move || func(receiver));
+
+    PriorityTask { sender, handle }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priority_messages_overtake_waiting_messages() {
+        let (start, started) = channel::<()>();
+        let task = spawn_priority_task(move |mut receiver| {
+            started.recv().unwrap();
+            let mut received = Vec::new();
+            while let Ok(val) = receiver.recv() {
+                received.push(val);
+            }
+            received
+        });
+
+        task.send(1);
+        task.send(2);
+        task.send_priority(10);
+        task.send(3);
+        task.send_priority(11);
+        start.send(()).unwrap();
+
+        assert_eq!(task.drain(), vec![10, 11, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_lanes_keep_their_own_order() {
+        let (start, started) = channel::<()>();
+        let task = spawn_priority_task(move |mut receiver| {
+            started.recv().unwrap();
+            (0..6).map(|_| receiver.recv().unwrap()).collect::<Vec<_>>()
+        });
+
+        for i in 0..3 {
+            task.send(i);
+            task.send_priority(100 + i);
+        }
+        start.send(()).unwrap();
+
+        assert_eq!(task.join(), vec![100, 101, 102, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_aging_lets_normal_messages_through() {
+        let (start, started) = channel::<()>();
+        let task = spawn_priority_task(move |mut receiver| {
+            receiver.set_aging(2);
+            started.recv().unwrap();
+            let mut received = Vec::new();
+            while let Ok(val) = receiver.recv() {
+                received.push(val);
+            }
+            received
+        });
+
+        task.send(1);
+        task.send(2);
+        for i in 0..5 {
+            task.send_priority(100 + i);
+        }
+        start.send(()).unwrap();
+
+        assert_eq!(task.drain(), vec![100, 101, 1, 102, 103, 2, 104]);
+    }
+
+    #[test]
+    fn test_try_recv_on_empty_lanes() {
+        let (ready, is_ready) = channel::<()>();
+        let task = spawn_priority_task(move |mut receiver: PriorityReceiver<u32>| {
+            assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+            ready.send(()).unwrap();
+            receiver.recv()
+        });
+
+        is_ready.recv().unwrap();
+        task.send(5);
+        assert_eq!(task.join(), Ok(5));
+    }
+
+    #[test]
+    fn test_try_recv_reports_disconnected_once_lanes_are_empty() {
+        let (start, started) = channel::<()>();
+        let task = spawn_priority_task(move |mut receiver| {
+            started.recv().unwrap();
+            let mut received = Vec::new();
+            loop {
+                match receiver.try_recv() {
+                    Ok(val) => received.push(val),
+                    Err(TryRecvError::Empty) => std::thread::yield_now(),
+                    Err(TryRecvError::Disconnected) => return received,
+                }
+            }
+        });
+
+        task.send(1);
+        task.send_priority(2);
+        start.send(()).unwrap();
+
+        assert_eq!(task.drain(), vec![2, 1]);
+    }
+}
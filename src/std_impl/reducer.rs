@@ -0,0 +1,41 @@
+use super::{Task, spawn_task};
+
+/// Spawns a task folding every message into an accumulator, starting with
+/// `initial`. [`Task::drain`] (or `join` once all senders are gone) yields
+/// the final accumulator.
+pub fn spawn_reducer<M, A, F>(initial: A, fold: F) -> Task<M, A>
+where
+    M: Send + 'static,
+    A: Send + 'static,
+    F: FnMut(A, M) -> A + Send + 'static,
+{
+    spawn_task(move |receiver| receiver.iter().fold(initial, fold))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reducer_yields_final_accumulator() {
+        let task = spawn_reducer(0, |sum, val: u32| sum + val);
+        for i in 1..=10 {
+            task.send(i);
+        }
+        assert_eq!(task.drain(), 55);
+    }
+
+    #[test]
+    fn test_reducer_with_structured_accumulator() {
+        let task = spawn_reducer(Vec::new(), |mut words, word: &str| {
+            if !words.contains(&word) {
+                words.push(word);
+            }
+            words
+        });
+        for word in ["a", "b", "a", "c"] {
+            task.send(word);
+        }
+        assert_eq!(task.drain(), vec!["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::mpsc::{Receiver, Sender, channel};
+
+/// A request bundled with the channel to answer it on.
+///
+/// The requester keeps the receiver returned by [`ReplyTo::new`] and sends
+/// the `ReplyTo` as (part of) a message; the task answers with
+/// [`ReplyTo::respond`].
+pub struct ReplyTo<Req, Resp> {
+    pub request: Req,
+    reply_to: Sender<Resp>,
+}
+
+impl<Req, Resp> ReplyTo<Req, Resp> {
+    pub fn new(request: Req) -> (Self, Receiver<Resp>) {
+        let (reply_to, reply) = channel();
+        (ReplyTo { request, reply_to }, reply)
+    }
+
+    /// Hands the response back if the requester stopped waiting.
+    pub fn respond(self, response: Resp) -> Result<(), Resp> {
+        self.reply_to.send(response).map_err(|error| error.0)
+    }
+
+    /// Separates the request from the means to answer it, e.g. to move the
+    /// request into a helper that computes the response.
+    pub fn split(self) -> (Req, ReplyTo<(), Resp>) {
+        (
+            self.request,
+            ReplyTo {
+                request: (),
+                reply_to: self.reply_to,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_task;
+
+    enum Message {
+        Double(ReplyTo<u32, u32>),
+        Len(ReplyTo<String, usize>),
+    }
+
+    #[test]
+    fn test_requests_are_answered_through_reply_to() {
+        let task = spawn_task(|receiver| {
+            for message in receiver.iter() {
+                match message {
+                    Message::Double(message) => {
+                        let val = message.request;
+                        message.respond(val * 2).unwrap();
+                    }
+                    Message::Len(message) => {
+                        let (text, reply_to) = message.split();
+                        reply_to.respond(text.len()).unwrap();
+                    }
+                }
+            }
+        });
+        let sender = task.sender();
+
+        let (message, reply) = ReplyTo::new(21);
+        sender.send(Message::Double(message));
+        assert_eq!(reply.recv(), Ok(42));
+
+        let (message, reply) = ReplyTo::new("hello".to_string());
+        sender.send(Message::Len(message));
+        assert_eq!(reply.recv(), Ok(5));
+
+        drop(sender);
+        task.drain();
+    }
+
+    #[test]
+    fn test_respond_returns_unwanted_responses() {
+        let (message, reply) = ReplyTo::<(), u32>::new(());
+        drop(reply);
+        assert_eq!(message.respond(7), Err(7));
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{Receiver, RecvError, TryRecvError},
+};
+
+/// Wraps a task's receiver so the task can park messages it is not ready
+/// for yet and replay them later.
+///
+/// [`StashReceiver::unstash_all`] puts the stashed messages back in front
+/// of the mailbox, in the order they were stashed.
+pub struct StashReceiver<M> {
+    receiver: Receiver<M>,
+    stash: VecDeque<M>,
+    replay: VecDeque<M>,
+}
+
+impl<M> StashReceiver<M> {
+    pub fn new(receiver: Receiver<M>) -> Self {
+        StashReceiver {
+            receiver,
+            stash: VecDeque::new(),
+            replay: VecDeque::new(),
+        }
+    }
+
+    /// Blocks until a message is available, unstashed messages first.
+    pub fn recv(&mut self) -> Result<M, RecvError> {
+        match self.replay.pop_front() {
+            Some(payload) => Ok(payload),
+            None => self.receiver.recv(),
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Result<M, TryRecvError> {
+        match self.replay.pop_front() {
+            Some(payload) => Ok(payload),
+            None => self.receiver.try_recv(),
+        }
+    }
+
+    pub fn stash(&mut self, payload: M) {
+        self.stash.push_back(payload);
+    }
+
+    /// Messages stashed and not handed out again yet.
+    pub fn stashed(&self) -> usize {
+        self.stash.len() + self.replay.len()
+    }
+
+    /// Replays all stashed messages before anything else, including
+    /// messages of an earlier `unstash_all` that were not received yet.
+    pub fn unstash_all(&mut self) {
+        let mut stash = std::mem::take(&mut self.stash);
+        stash.append(&mut self.replay);
+        self.replay = stash;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_task;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Message {
+        Ready,
+        Work(u32),
+    }
+
+    #[test]
+    fn test_stashed_messages_are_replayed_once_ready() {
+        let task = spawn_task(|receiver| {
+            let mut receiver = StashReceiver::new(receiver);
+            while let Ok(message) = receiver.recv() {
+                match message {
+                    Message::Ready => break,
+                    work => receiver.stash(work),
+                }
+            }
+            assert_eq!(receiver.stashed(), 2);
+            receiver.unstash_all();
+
+            let mut handled = Vec::new();
+            while let Ok(message) = receiver.recv() {
+                handled.push(message);
+            }
+            handled
+        });
+
+        task.send(Message::Work(1));
+        task.send(Message::Work(2));
+        task.send(Message::Ready);
+        task.send(Message::Work(3));
+
+        assert_eq!(
+            task.drain(),
+            vec![Message::Work(1), Message::Work(2), Message::Work(3)]
+        );
+    }
+
+    #[test]
+    fn test_unstash_all_goes_before_earlier_replays() {
+        let (sender, receiver) = std::sync::mpsc::channel::<u32>();
+        let mut receiver = StashReceiver::new(receiver);
+        sender.send(9).unwrap();
+
+        receiver.stash(1);
+        receiver.stash(2);
+        receiver.unstash_all();
+        assert_eq!(receiver.try_recv(), Ok(1));
+        receiver.stash(3);
+        receiver.unstash_all();
+
+        assert_eq!(receiver.try_recv(), Ok(3));
+        assert_eq!(receiver.try_recv(), Ok(2));
+        assert_eq!(receiver.try_recv(), Ok(9));
+        assert_eq!(receiver.try_recv(), Err(TryRecvError::Empty));
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{Receiver, RecvError, Sender, channel},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+/// How many of the most recent queueing delays a [`QueueLatency`] keeps.
+const SAMPLES: usize = 1024;
+
+/// The queueing delays of the most recent messages of a timed task, i.e.
+/// how long they waited in the mailbox before being received.
+#[derive(Debug, Clone, Default)]
+pub struct QueueLatency {
+    samples: VecDeque<Duration>,
+}
+
+impl QueueLatency {
+    pub(crate) fn record(&mut self, delay: Duration) {
+        if self.samples.len() == SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(delay);
+    }
+
+    pub fn count(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// The delay that `percentile` percent of the recent messages did not
+    /// exceed, e.g. `percentile(99.0)`.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        assert!(
+            (0.0..=100.0).contains(&percentile),
+            "percentiles are within 0-100"
+        );
+        let mut sorted: Vec<_> = self.samples.iter().copied().collect();
+        sorted.sort();
+        let last = sorted.len().checked_sub(1)?;
+        let rank = (percentile / 100.0 * last as f64).round() as usize;
+        Some(sorted[rank])
+    }
+}
+
+/// A task whose mailbox stamps every message with the time it was sent.
+pub struct TimedTask<M, R> {
+    sender: TimedSender<M>,
+    handle: JoinHandle<R>,
+}
+
+impl<M, R> TimedTask<M, R> {
+    pub fn send(&self, payload: M) {
+        self.sender.send(payload)
+    }
+
+    pub fn sender(&self) -> TimedSender<M> {
+        self.sender.clone()
+    }
+
+    pub fn join(self) -> R {
+        self.handle.join().unwrap()
+    }
+
+    pub fn drain(self) -> R {
+        drop(self.sender);
+        self.handle.join().unwrap()
+    }
+}
+
+pub struct TimedSender<M>(Sender<(Instant, M)>);
+
+impl<M> Clone for TimedSender<M> {
+    fn clone(&self) -> Self {
+        TimedSender(self.0.clone())
+    }
+}
+
+impl<M> TimedSender<M> {
+    pub fn send(&self, payload: M) {
+        self.0
+            .send((Instant::now(), payload))
+            .unwrap_or_else(|_| panic!("task mailbox is closed"))
+    }
+}
+
+pub struct TimedReceiver<M> {
+    receiver: Receiver<(Instant, M)>,
+    sent_at: Option<Instant>,
+    latency: QueueLatency,
+}
+
+impl<M> TimedReceiver<M> {
+    pub fn recv(&mut self) -> Result<M, RecvError> {
+        let (sent_at, payload) = self.receiver.recv()?;
+        self.latency.record(sent_at.elapsed());
+        self.sent_at = Some(sent_at);
+        Ok(payload)
+    }
+
+    /// Time since the message received last was sent: its queueing delay
+    /// plus the time spent handling it so far.
+    pub fn message_age(&self) -> Option<Duration> {
+        self.sent_at.map(|sent_at| sent_at.elapsed())
+    }
+
+    pub fn latency(&self) -> &QueueLatency {
+        &self.latency
+    }
+}
+
+pub fn spawn_timed_task<M, R, Func>(func: Func) -> TimedTask<M, R>
+where
+    M: Send + 'static,
+    R: Send + 'static,
+    Func: FnOnce(TimedReceiver<M>) -> R + Send + 'static,
+{
+    let (sender, receiver) = channel::<(Instant, M)>();
+    let receiver = TimedReceiver {
+        receiver,
+        sent_at: None,
+        latency: QueueLatency::default(),
+    };
+    let handle = std::thread::spawn(move || func(receiver));
+
+    TimedTask {
+        sender: TimedSender(sender),
+        handle,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timed_receiver_measures_queueing_delay() {
+        let (start, started) = channel::<()>();
+        let task = spawn_timed_task(move |mut receiver| {
+            started.recv().unwrap();
+            let mut ages = Vec::new();
+            while receiver.recv().is_ok() {
+                ages.push(receiver.message_age().unwrap());
+            }
+            (ages, receiver.latency().clone())
+        });
+
+        task.send(1);
+        std::thread::sleep(Duration::from_millis(30));
+        task.send(2);
+        start.send(()).unwrap();
+
+        let (ages, latency) = task.drain();
+        assert!(ages[0] >= Duration::from_millis(30));
+        assert!(ages[1] < ages[0]);
+        assert_eq!(latency.count(), 2);
+        assert!(latency.percentile(100.0).unwrap() >= Duration::from_millis(30));
+        assert!(latency.percentile(0.0).unwrap() < Duration::from_millis(30));
+    }
+
+    #[test]
+    fn test_queue_latency_percentiles() {
+        let mut latency = QueueLatency::default();
+        assert_eq!(latency.percentile(50.0), None);
+
+        for millis in 1..=100 {
+            latency.record(Duration::from_millis(millis));
+        }
+        assert_eq!(latency.percentile(0.0), Some(Duration::from_millis(1)));
+        assert_eq!(latency.percentile(50.0), Some(Duration::from_millis(51)));
+        assert_eq!(latency.percentile(99.0), Some(Duration::from_millis(99)));
+        assert_eq!(latency.percentile(100.0), Some(Duration::from_millis(100)));
+    }
+}
@@ -1,9 +1,58 @@
 use std::future::Future;
 use tokio::{
-    sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
+    sync::mpsc::{UnboundedReceiver, UnboundedSender, error::SendError, unbounded_channel},
     task::JoinHandle,
 };
 
+use crate::TaskError;
+
+mod barrier;
+mod concurrent;
+mod duplex;
+mod dyn_actor;
+mod entity;
+mod exclusive;
+mod hooked;
+mod idle;
+mod keyed;
+mod latch;
+mod lazy;
+mod per_core;
+mod priority;
+mod process;
+mod reducer;
+mod reply;
+mod scheduler;
+mod service;
+mod shutdown;
+#[cfg(feature = "signals")]
+pub mod signals;
+mod stash;
+mod timed;
+mod two_phase;
+pub use self::barrier::*;
+pub use self::concurrent::*;
+pub use self::duplex::*;
+pub use self::dyn_actor::*;
+pub use self::entity::*;
+pub use self::exclusive::*;
+pub use self::hooked::*;
+pub use self::idle::*;
+pub use self::keyed::*;
+pub use self::latch::*;
+pub use self::lazy::*;
+pub use self::per_core::*;
+pub use self::priority::*;
+pub use self::process::*;
+pub use self::reducer::*;
+pub use self::reply::*;
+pub use self::scheduler::*;
+pub use self::service::*;
+pub use self::shutdown::*;
+pub use self::stash::*;
+pub use self::timed::*;
+pub use self::two_phase::*;
+
 #[derive(Clone)]
 struct AsyncMailbox<T>(UnboundedSender<T>);
 
@@ -25,6 +74,71 @@ where
     }
 }
 
+impl<T, R> AsyncTask<T, R> {
+    /// Returns a cloneable handle for sending into this task's mailbox.
+    pub fn sender(&self) -> AsyncTaskSender<T> {
+        AsyncTaskSender(self.mailbox.0.clone())
+    }
+
+    /// Drops the task's own sender and waits for the task to handle the
+    /// remaining messages and return. The mailbox only closes once every
+    /// [`AsyncTaskSender`] clone is gone as well, e.g. signal subscriptions;
+    /// until then this waits.
+    pub async fn drain(self) -> R {
+        drop(self.mailbox);
+        self.handle.await.unwrap()
+    }
+}
+
+/// Async counterpart of [`TaskSender`](crate::TaskSender), with the same
+/// per-sender ordering.
+pub struct AsyncTaskSender<T>(UnboundedSender<T>);
+
+impl<T> Clone for AsyncTaskSender<T> {
+    fn clone(&self) -> Self {
+        AsyncTaskSender(self.0.clone())
+    }
+}
+
+impl<T> AsyncTaskSender<T> {
+    pub async fn send(&self, payload: T) {
+        self.0
+            .send(payload)
+            .unwrap_or_else(|_| panic!("task mailbox is closed"))
+    }
+
+    /// Sends without awaiting, so it can be used from plain threads that
+    /// are not part of any tokio runtime.
+    pub fn send_from_thread(&self, payload: T) {
+        self.0
+            .send(payload)
+            .unwrap_or_else(|_| panic!("task mailbox is closed"))
+    }
+
+    /// Like [`AsyncTaskSender::send_from_thread`], but hands the payload
+    /// back instead of panicking when the task is gone.
+    pub fn try_send(&self, payload: T) -> Result<(), SendError<T>> {
+        self.0.send(payload)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.0.is_closed()
+    }
+}
+
+impl<T, R, E> AsyncTask<T, Result<R, E>> {
+    /// Joins a fallible task, telling a returned error apart from a panic or
+    /// an abort.
+    pub async fn try_join(self) -> Result<R, TaskError<E>> {
+        match self.handle.await {
+            Ok(result) => result.map_err(TaskError::Failed),
+            Err(error) if error.is_panic() => Err(TaskError::Panicked(error.into_panic())),
+            Err(_) => Err(TaskError::Aborted),
+        }
+    }
+}
+
+#[cfg(feature = "macros")]
 #[macro_export]
 macro_rules! async_proc {
     ($($content:tt)*) => {
@@ -55,6 +169,28 @@ where
     }
 }
 
+/// Spawns a task that awaits `init` before running `func`. Messages sent in
+/// the meantime wait in the mailbox; if `init` fails, the task returns its
+/// error without running `func`.
+pub fn spawn_async_task_with_init<M, S, E, Init, R, Output, Func>(
+    init: Init,
+    func: Func,
+) -> AsyncTask<M, Result<Output, E>>
+where
+    M: Send + 'static,
+    S: Send + 'static,
+    E: Send + 'static,
+    Init: Future<Output = Result<S, E>> + Send + 'static,
+    R: Send + 'static + Future<Output = Output>,
+    Output: Send + 'static,
+    Func: FnOnce(S, UnboundedReceiver<M>) -> R + Send + 'static,
+{
+    spawn_async_task(move |receiver| async move {
+        let state = init.await?;
+        Ok(func(state, receiver).await)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -161,6 +297,186 @@ mod tests {
         assert_eq!(result, 42);
     }
 
+    #[tokio::test]
+    async fn test_async_drain_finishes_pending_messages() {
+        let task = spawn_async_task(|mut receiver| async move {
+            let mut total = 0;
+            while let Some(val) = receiver.recv().await {
+                tokio::task::yield_now().await;
+                total += val;
+            }
+            total
+        });
+
+        for i in 1..=10 {
+            task.send(i).await;
+        }
+
+        let result = task.drain().await;
+        assert_eq!(result, 55);
+    }
+
+    #[tokio::test]
+    async fn test_async_try_join_distinguishes_errors_panics_and_aborts() {
+        let failed = spawn_async_task(|mut receiver| async move {
+            let val: u32 = receiver.recv().await.unwrap();
+            Err::<u32, _>(format!("bad input {val}"))
+        });
+        failed.send(2).await;
+        assert_eq!(
+            failed.try_join().await.unwrap_err().into_failure(),
+            Some("bad input 2".to_string())
+        );
+
+        let panicked = spawn_async_task(|mut receiver| async move {
+            let val: u32 = receiver.recv().await.unwrap();
+            if val > 0 {
+                panic!("boom {val}");
+            }
+            Ok::<_, String>(val)
+        });
+        panicked.send(3).await;
+        assert!(panicked.try_join().await.unwrap_err().is_panic());
+
+        let aborted = spawn_async_task(|mut receiver| async move {
+            let val: u32 = receiver.recv().await.unwrap();
+            Ok::<_, String>(val)
+        });
+        aborted.handle.abort();
+        assert!(aborted.try_join().await.unwrap_err().is_aborted());
+    }
+
+    #[tokio::test]
+    async fn test_async_try_join_without_clone_messages() {
+        struct Job(u32);
+
+        let task = spawn_async_task(|mut receiver: UnboundedReceiver<Job>| async move {
+            receiver.recv().await.map(|job| job.0).ok_or("no job")
+        });
+        task.sender().send(Job(7)).await;
+        assert_eq!(task.try_join().await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_async_senders_share_the_mailbox() {
+        let task = spawn_async_task(|mut receiver| async move {
+            let mut total = 0;
+            while let Some(val) = receiver.recv().await {
+                total += val;
+            }
+            total
+        });
+
+        let first = task.sender();
+        let second = first.clone();
+        let producer = tokio::spawn(async move {
+            for i in 1..=5 {
+                second.send(i).await;
+            }
+        });
+        first.send(100).await;
+        producer.await.unwrap();
+        drop(first);
+
+        assert_eq!(task.drain().await, 115);
+    }
+
+    #[tokio::test]
+    async fn test_async_messages_keep_per_sender_order() {
+        let task = spawn_async_task(|mut receiver| async move {
+            let mut received = Vec::new();
+            while let Some(message) = receiver.recv().await {
+                received.push(message);
+            }
+            received
+        });
+
+        let mut producers = Vec::new();
+        for producer in 0..4u32 {
+            let sender = task.sender();
+            producers.push(tokio::spawn(async move {
+                for seq in 0..250u32 {
+                    sender.send((producer, seq)).await;
+                    if seq % 50 == 0 {
+                        tokio::task::yield_now().await;
+                    }
+                }
+            }));
+        }
+        for producer in producers {
+            producer.await.unwrap();
+        }
+
+        let received = task.drain().await;
+        assert_eq!(received.len(), 1000);
+        for producer in 0..4 {
+            let sequence: Vec<u32> = received
+                .iter()
+                .filter(|(from, _)| *from == producer)
+                .map(|(_, seq)| *seq)
+                .collect();
+            assert_eq!(sequence, (0..250).collect::<Vec<_>>());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_send_from_thread_without_runtime() {
+        let task = spawn_async_task(|mut receiver| async move {
+            let mut total = 0;
+            while let Some(val) = receiver.recv().await {
+                total += val;
+            }
+            total
+        });
+
+        let sender = task.sender();
+        std::thread::spawn(move || {
+            assert!(tokio::runtime::Handle::try_current().is_err());
+            for i in 1..=4 {
+                sender.send_from_thread(i);
+            }
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(task.drain().await, 10);
+    }
+
+    #[tokio::test]
+    async fn test_async_messages_sent_during_init_are_delivered_afterwards() {
+        let (ready, is_ready) = tokio::sync::oneshot::channel::<()>();
+        let task = spawn_async_task_with_init(
+            async move {
+                is_ready.await.unwrap();
+                Ok::<_, String>(100)
+            },
+            |offset, mut receiver| async move {
+                let mut values = Vec::new();
+                while let Some(val) = receiver.recv().await {
+                    values.push(val + offset);
+                }
+                values
+            },
+        );
+
+        task.send(1).await;
+        task.send(2).await;
+        ready.send(()).unwrap();
+
+        assert_eq!(task.drain().await, Ok(vec![101, 102]));
+    }
+
+    #[tokio::test]
+    async fn test_async_failed_init_skips_the_task_body() {
+        let task = spawn_async_task_with_init(
+            async { Err::<u32, _>("no database".to_string()) },
+            |_, _receiver: UnboundedReceiver<u32>| async move { unreachable!() },
+        );
+
+        let error = task.try_join().await.unwrap_err();
+        assert_eq!(error.into_failure(), Some("no database".to_string()));
+    }
+
     #[tokio::test]
     async fn test_async_different_number_types() {
         let task = spawn_async_task(|mut receiver| async move {
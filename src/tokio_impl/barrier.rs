@@ -0,0 +1,231 @@
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+use super::AsyncTaskSender;
+
+/// Sent to subscribers of a [`Barrier`] whenever all parties arrived.
+/// `phase` counts the releases, starting at zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Released {
+    pub phase: u64,
+}
+
+type Subscriber = Box<dyn Fn(Released) -> bool + Send>;
+
+struct State {
+    arrived: usize,
+    phase: u64,
+    subscribers: Vec<Subscriber>,
+}
+
+struct Inner {
+    parties: usize,
+    state: Mutex<State>,
+    released: watch::Sender<u64>,
+}
+
+/// Lets `n` tasks wait for each other before moving on to the next phase.
+///
+/// The barrier is reusable: once all parties arrived, they are released
+/// together and the next `n` arrivals make up the next phase. Tasks that are
+/// driven by their mailbox rather than by awaiting can [`subscribe`] to get
+/// a [`Released`] message for every phase instead.
+///
+/// [`subscribe`]: Barrier::subscribe
+#[derive(Clone)]
+pub struct Barrier {
+    inner: Arc<Inner>,
+}
+
+impl Barrier {
+    pub fn new(parties: usize) -> Self {
+        assert!(parties > 0, "a barrier needs at least one party");
+
+        Barrier {
+            inner: Arc::new(Inner {
+                parties,
+                state: Mutex::new(State {
+                    arrived: 0,
+                    phase: 0,
+                    subscribers: Vec::new(),
+                }),
+                released: watch::Sender::new(0),
+            }),
+        }
+    }
+
+    pub fn parties(&self) -> usize {
+        self.inner.parties
+    }
+
+    /// Waits until all parties of the current phase arrived. Dropping the
+    /// future before the release withdraws the arrival again, e.g. when it
+    /// is raced against a timeout.
+    pub async fn arrive(&self) -> Released {
+        let released = self.arrive_detached();
+        let mut arrival = Arrival {
+            barrier: self,
+            phase: Some(released.phase),
+        };
+        let mut phases = self.inner.released.subscribe();
+        // the sender lives in `self`, so this cannot fail
+        let _ = phases
+            .wait_for(|completed| *completed > released.phase)
+            .await;
+        arrival.phase = None;
+        released
+    }
+
+    /// Counts as an arrival without waiting for the release. Meant for tasks
+    /// that learn about the release through [`Barrier::subscribe`].
+    pub fn arrive_detached(&self) -> Released {
+        let mut state = self.inner.state.lock().unwrap();
+        let released = Released { phase: state.phase };
+
+        state.arrived += 1;
+        if state.arrived == self.inner.parties {
+            state.arrived = 0;
+            state.phase += 1;
+            state.subscribers.retain(|subscriber| subscriber(released));
+            self.inner.released.send_replace(state.phase);
+        }
+        released
+    }
+
+    /// Delivers a [`Released`] message into the mailbox behind `sender` for
+    /// every following release, until that task is gone.
+    pub fn subscribe<M>(&self, sender: AsyncTaskSender<M>)
+    where
+        M: From<Released> + Send + 'static,
+    {
+        self.inner
+            .state
+            .lock()
+            .unwrap()
+            .subscribers
+            .push(Box::new(move |released| {
+                sender.try_send(released.into()).is_ok()
+            }));
+    }
+}
+
+/// Withdraws an arrival whose [`Barrier::arrive`] future was dropped while
+/// its phase was still waiting.
+struct Arrival<'a> {
+    barrier: &'a Barrier,
+    phase: Option<u64>,
+}
+
+impl Drop for Arrival<'_> {
+    fn drop(&mut self) {
+        let mut state = self.barrier.inner.state.lock().unwrap();
+        if self.phase == Some(state.phase) {
+            state.arrived -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_async_task;
+    use std::time::Duration;
+    use tokio::sync::mpsc::UnboundedReceiver;
+
+    #[tokio::test]
+    async fn test_async_all_parties_are_released_together() {
+        let barrier = Barrier::new(3);
+        let (log, mut entries) = tokio::sync::mpsc::unbounded_channel();
+
+        let mut tasks = Vec::new();
+        for id in 0..3 {
+            let barrier = barrier.clone();
+            let log = log.clone();
+            tasks.push(spawn_async_task(
+                move |_receiver: UnboundedReceiver<()>| async move {
+                    for phase in 0..2 {
+                        log.send(("arrive", phase)).unwrap();
+                        assert_eq!(barrier.arrive().await, Released { phase });
+                        log.send(("leave", phase)).unwrap();
+                    }
+                    id
+                },
+            ));
+        }
+        for task in tasks {
+            task.join().await;
+        }
+        drop(log);
+
+        let mut order = Vec::new();
+        while let Some(entry) = entries.recv().await {
+            order.push(entry);
+        }
+        // nobody leaves a phase before everybody arrived in it
+        for phase in 0..2 {
+            let last_arrival = order
+                .iter()
+                .rposition(|entry| *entry == ("arrive", phase))
+                .unwrap();
+            let first_leave = order
+                .iter()
+                .position(|entry| *entry == ("leave", phase))
+                .unwrap();
+            assert!(last_arrival < first_leave);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_async_barrier_waits_for_missing_parties() {
+        let barrier = Barrier::new(2);
+
+        let blocked = tokio::time::timeout(Duration::from_millis(20), barrier.arrive()).await;
+        assert!(blocked.is_err());
+
+        // the timed out arrival was withdrawn
+        let alone = tokio::time::timeout(Duration::from_millis(20), barrier.arrive()).await;
+        assert!(alone.is_err());
+
+        let other = barrier.clone();
+        let waiter = tokio::spawn(async move { other.arrive().await });
+        assert_eq!(barrier.arrive().await, Released { phase: 0 });
+        assert_eq!(waiter.await.unwrap(), Released { phase: 0 });
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Message {
+        Released(u64),
+    }
+
+    impl From<Released> for Message {
+        fn from(released: Released) -> Self {
+            Message::Released(released.phase)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_subscribers_receive_released_messages() {
+        let barrier = Barrier::new(2);
+        let task = spawn_async_task(|mut receiver: UnboundedReceiver<Message>| async move {
+            let mut received = Vec::new();
+            while let Some(message) = receiver.recv().await {
+                received.push(message);
+            }
+            received
+        });
+        barrier.subscribe(task.sender());
+
+        for _ in 0..2 {
+            let other = barrier.clone();
+            let waiter = tokio::spawn(async move { other.arrive().await });
+            barrier.arrive_detached();
+            waiter.await.unwrap();
+        }
+
+        drop(barrier);
+        assert_eq!(
+            task.drain().await,
+            vec![Message::Released(0), Message::Released(1)]
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use std::{collections::VecDeque, future::Future};
+use tokio::{
+    sync::mpsc::{UnboundedReceiver, unbounded_channel},
+    task::{JoinError, JoinHandle, JoinSet},
+};
+
+use super::{AsyncTask, spawn_async_task};
+
+/// Spawns a task handling up to `max_in_flight` messages at once, each in
+/// its own tokio task. Results arrive on the returned receiver in the
+/// order the handlers finished.
+///
+/// Once the mailbox is closed, the task waits for the running handlers and
+/// then finishes. A panicking handler takes the task down with it.
+pub fn spawn_concurrent_task<M, O, Fut, F>(
+    max_in_flight: usize,
+    handler: F,
+) -> (AsyncTask<M, ()>, UnboundedReceiver<O>)
+where
+    M: Send + 'static,
+    O: Send + 'static,
+    Fut: Future<Output = O> + Send + 'static,
+    F: Fn(M) -> Fut + Send + 'static,
+{
+    assert!(
+        max_in_flight > 0,
+        "at least one message has to be in flight"
+    );
+
+    let (output, outputs) = unbounded_channel();
+    let task = spawn_async_task(move |mut receiver: UnboundedReceiver<M>| async move {
+        let mut running = JoinSet::new();
+        loop {
+            tokio::select! {
+                message = receiver.recv(), if running.len() < max_in_flight => match message {
+                    Some(message) => {
+                        running.spawn(handler(message));
+                    }
+                    None => break,
+                },
+                Some(result) = running.join_next() => {
+                    // nobody reading the results is fine for side-effect-only handlers
+                    let _ = output.send(handled(result));
+                }
+            }
+        }
+        while let Some(result) = running.join_next().await {
+            let _ = output.send(handled(result));
+        }
+    });
+
+    (task, outputs)
+}
+
+/// Like [`spawn_concurrent_task`], but the results arrive in the order
+/// the messages were received, no matter which handler finishes first.
+///
+/// Finished results wait in a reorder buffer until all earlier ones are
+/// out. `max_in_flight` bounds the running handlers and the buffer
+/// together, so a slow message holds back new ones rather than letting
+/// the buffer grow.
+pub fn spawn_ordered_concurrent_task<M, O, Fut, F>(
+    max_in_flight: usize,
+    handler: F,
+) -> (AsyncTask<M, ()>, UnboundedReceiver<O>)
+where
+    M: Send + 'static,
+    O: Send + 'static,
+    Fut: Future<Output = O> + Send + 'static,
+    F: Fn(M) -> Fut + Send + 'static,
+{
+    assert!(
+        max_in_flight > 0,
+        "at least one message has to be in flight"
+    );
+
+    let (output, outputs) = unbounded_channel();
+    let task = spawn_async_task(move |mut receiver: UnboundedReceiver<M>| async move {
+        let mut pending = Pending(VecDeque::new());
+        let pending = &mut pending.0;
+        loop {
+            tokio::select! {
+                message = receiver.recv(), if pending.len() < max_in_flight => match message {
+                    Some(message) => pending.push_back(tokio::spawn(handler(message))),
+                    None => break,
+                },
+                result = async { pending.front_mut().expect("pending is not empty").await },
+                    if !pending.is_empty() =>
+                {
+                    pending.pop_front();
+                    let _ = output.send(handled(result));
+                }
+            }
+        }
+        while let Some(handle) = pending.front_mut() {
+            let result = handle.await;
+            pending.pop_front();
+            let _ = output.send(handled(result));
+        }
+    });
+
+    (task, outputs)
+}
+
+/// Handlers of the ordered task, in input order. They are aborted along
+/// with the task, like the ones in the `JoinSet` of the unordered one.
+struct Pending<O>(VecDeque<JoinHandle<O>>);
+
+impl<O> Drop for Pending<O> {
+    fn drop(&mut self) {
+        for handle in &self.0 {
+            handle.abort();
+        }
+    }
+}
+
+fn handled<O>(result: Result<O, JoinError>) -> O {
+    result.unwrap_or_else(|error| std::panic::resume_unwind(error.into_panic()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_async_messages_are_handled_concurrently() {
+        let started = tokio::time::Instant::now();
+        let (task, mut outputs) = spawn_concurrent_task(4, |delay: u64| async move {
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            delay
+        });
+
+        for delay in [80, 20, 60, 40] {
+            task.send(delay).await;
+        }
+        task.drain().await;
+
+        let mut finished = Vec::new();
+        while let Some(delay) = outputs.recv().await {
+            finished.push(delay);
+        }
+        assert_eq!(finished, vec![20, 40, 60, 80]);
+        // all four ran at once, sequential handling would take 200ms
+        assert_eq!(started.elapsed(), Duration::from_millis(80));
+    }
+
+    #[tokio::test]
+    async fn test_async_in_flight_messages_are_limited() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let (task, mut outputs) = {
+            let running = running.clone();
+            let peak = peak.clone();
+            spawn_concurrent_task(2, move |id: u32| {
+                let running = running.clone();
+                let peak = peak.clone();
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    id
+                }
+            })
+        };
+
+        for id in 0..10 {
+            task.send(id).await;
+        }
+        task.drain().await;
+
+        let mut handled = 0;
+        while outputs.recv().await.is_some() {
+            handled += 1;
+        }
+        assert_eq!(handled, 10);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_async_ordered_results_follow_input_order() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (task, mut outputs) = {
+            let running = running.clone();
+            let peak = peak.clone();
+            spawn_ordered_concurrent_task(4, move |delay: u64| {
+                let running = running.clone();
+                let peak = peak.clone();
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    delay
+                }
+            })
+        };
+
+        for delay in [80, 20, 60, 40, 0] {
+            task.send(delay).await;
+        }
+        task.drain().await;
+
+        let mut finished = Vec::new();
+        while let Some(delay) = outputs.recv().await {
+            finished.push(delay);
+        }
+        assert_eq!(finished, vec![80, 20, 60, 40, 0]);
+        assert!(peak.load(Ordering::SeqCst) > 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_async_ordered_handlers_stop_with_the_task() {
+        let finished = Arc::new(AtomicUsize::new(0));
+        let (task, _outputs) = {
+            let finished = finished.clone();
+            spawn_ordered_concurrent_task(4, move |()| {
+                let finished = finished.clone();
+                async move {
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                    finished.fetch_add(1, Ordering::SeqCst);
+                }
+            })
+        };
+
+        task.send(()).await;
+        task.send(()).await;
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        task.handle.abort();
+        assert!(task.handle.await.unwrap_err().is_cancelled());
+
+        tokio::time::sleep(Duration::from_secs(20)).await;
+        assert_eq!(finished.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,234 @@
+use std::future::Future;
+use tokio::{
+    sync::mpsc::{
+        UnboundedReceiver, UnboundedSender,
+        error::{SendError, TryRecvError},
+        unbounded_channel,
+    },
+    task::JoinHandle,
+};
+
+use super::{AsyncTask, AsyncTaskSender, spawn_async_task};
+
+/// Async counterpart of [`Outbox`](crate::Outbox).
+pub struct AsyncOutbox<T>(UnboundedSender<T>);
+
+impl<T> Clone for AsyncOutbox<T> {
+    fn clone(&self) -> Self {
+        AsyncOutbox(self.0.clone())
+    }
+}
+
+impl<T> AsyncOutbox<T> {
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.0.send(value)
+    }
+}
+
+/// Async counterpart of [`DuplexTask`](crate::DuplexTask).
+pub struct AsyncDuplexTask<In, Out, R> {
+    task: AsyncTask<In, R>,
+    outputs: UnboundedReceiver<Out>,
+}
+
+impl<In, Out, R> AsyncDuplexTask<In, Out, R> {
+    pub async fn send(&self, payload: In) {
+        self.task
+            .mailbox
+            .0
+            .send(payload)
+            .unwrap_or_else(|_| panic!("task mailbox is closed"))
+    }
+
+    pub fn sender(&self) -> AsyncTaskSender<In> {
+        self.task.sender()
+    }
+
+    /// Resolves to `None` once the task finished and all its outputs were
+    /// received.
+    pub async fn recv_output(&mut self) -> Option<Out> {
+        self.outputs.recv().await
+    }
+
+    pub fn try_recv_output(&mut self) -> Result<Out, TryRecvError> {
+        self.outputs.try_recv()
+    }
+
+    pub async fn drain(self) -> R {
+        self.task.drain().await
+    }
+
+    pub fn split(self) -> (AsyncTask<In, R>, UnboundedReceiver<Out>) {
+        (self.task, self.outputs)
+    }
+}
+
+pub fn spawn_async_duplex_task<In, Out, R, Output, Func>(
+    func: Func,
+) -> AsyncDuplexTask<In, Out, Output>
+where
+    In: Send + 'static,
+    Out: Send + 'static,
+    R: Send + 'static + Future<Output = Output>,
+    Output: Send + 'static,
+    Func: FnOnce(UnboundedReceiver<In>, AsyncOutbox<Out>) -> R + Send + 'static,
+{
+    let (outbox, outputs) = unbounded_channel();
+    let task = spawn_async_task(move |receiver| func(receiver, AsyncOutbox(outbox)));
+
+    AsyncDuplexTask { task, outputs }
+}
+
+/// Two duplex tasks wired into each other by [`connect`].
+///
+/// Each task's outputs are the other task's mailbox, so neither task sees
+/// its mailbox close on its own. [`Connection::shutdown`] unwires and
+/// stops both together.
+pub struct Connection<AIn, BIn, RA, RB> {
+    a: AsyncTask<AIn, RA>,
+    b: AsyncTask<BIn, RB>,
+    forwarders: [JoinHandle<()>; 2],
+}
+
+impl<AIn, BIn, RA, RB> Connection<AIn, BIn, RA, RB> {
+    pub fn sender_a(&self) -> AsyncTaskSender<AIn> {
+        self.a.sender()
+    }
+
+    pub fn sender_b(&self) -> AsyncTaskSender<BIn> {
+        self.b.sender()
+    }
+
+    /// Stops forwarding between the tasks, drops the connection's senders
+    /// and waits for both tasks. Outputs still in flight between them are
+    /// dropped. Like [`AsyncTask::drain`], this waits until every clone
+    /// handed out by [`Connection::sender_a`] and [`Connection::sender_b`]
+    /// is gone as well.
+    pub async fn shutdown(self) -> (RA, RB) {
+        for forwarder in self.forwarders {
+            forwarder.abort();
+            // the forwarder was either cancelled or already done
+            let _ = forwarder.await;
+        }
+        (self.a.drain().await, self.b.drain().await)
+    }
+}
+
+/// Feeds the outputs of `a` into the mailbox of `b` (converted by `a_to_b`)
+/// and the outputs of `b` into the mailbox of `a` (converted by `b_to_a`).
+pub fn connect<AIn, AOut, RA, BIn, BOut, RB, F, G>(
+    a: AsyncDuplexTask<AIn, AOut, RA>,
+    b: AsyncDuplexTask<BIn, BOut, RB>,
+    a_to_b: F,
+    b_to_a: G,
+) -> Connection<AIn, BIn, RA, RB>
+where
+    AIn: Send + 'static,
+    AOut: Send + 'static,
+    BIn: Send + 'static,
+    BOut: Send + 'static,
+    F: Fn(AOut) -> BIn + Send + 'static,
+    G: Fn(BOut) -> AIn + Send + 'static,
+{
+    let (a, a_outputs) = a.split();
+    let (b, b_outputs) = b.split();
+    let forwarders = [
+        forward(a_outputs, b.mailbox.0.clone(), a_to_b),
+        forward(b_outputs, a.mailbox.0.clone(), b_to_a),
+    ];
+
+    Connection { a, b, forwarders }
+}
+
+fn forward<Out, In, F>(
+    mut outputs: UnboundedReceiver<Out>,
+    mailbox: UnboundedSender<In>,
+    convert: F,
+) -> JoinHandle<()>
+where
+    Out: Send + 'static,
+    In: Send + 'static,
+    F: Fn(Out) -> In + Send + 'static,
+{
+    tokio::spawn(async move {
+        while let Some(output) = outputs.recv().await {
+            if mailbox.send(convert(output)).is_err() {
+                return;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_async_duplex_task_streams_outputs() {
+        let mut task = spawn_async_duplex_task(|mut receiver, outbox| async move {
+            let mut total = 0;
+            while let Some(val) = receiver.recv().await {
+                let val: u32 = val;
+                total += val;
+                outbox.send(total).unwrap();
+            }
+            total
+        });
+
+        task.send(1).await;
+        assert_eq!(task.recv_output().await, Some(1));
+        task.send(2).await;
+        task.send(3).await;
+        assert_eq!(task.recv_output().await, Some(3));
+        assert_eq!(task.recv_output().await, Some(6));
+
+        let (task, mut outputs) = task.split();
+        assert_eq!(task.drain().await, 6);
+        assert_eq!(outputs.recv().await, None);
+    }
+
+    #[derive(Debug)]
+    enum Ping {
+        Ball(u32),
+    }
+
+    #[derive(Debug)]
+    enum Pong {
+        Ball(u32),
+    }
+
+    #[tokio::test]
+    async fn test_async_connected_tasks_talk_to_each_other() {
+        let (done, finished) = tokio::sync::oneshot::channel();
+        let pinger = spawn_async_duplex_task(move |mut receiver, outbox| async move {
+            let mut done = Some(done);
+            let mut hits = 0;
+            while let Some(Pong::Ball(n)) = receiver.recv().await {
+                hits += 1;
+                if n == 0 {
+                    if let Some(done) = done.take() {
+                        let _ = done.send(());
+                    }
+                } else {
+                    let _ = outbox.send(Ping::Ball(n - 1));
+                }
+            }
+            hits
+        });
+        let ponger = spawn_async_duplex_task(|mut receiver, outbox| async move {
+            let mut hits = 0;
+            while let Some(Ping::Ball(n)) = receiver.recv().await {
+                hits += 1;
+                let _ = outbox.send(n);
+            }
+            hits
+        });
+
+        let connection = connect(pinger, ponger, |ping| ping, Pong::Ball);
+        connection.sender_b().send(Ping::Ball(4)).await;
+        finished.await.unwrap();
+
+        // the ponger served 4, 3, 2, 1 and 0, the pinger returned all of them
+        assert_eq!(connection.shutdown().await, (5, 5));
+    }
+}
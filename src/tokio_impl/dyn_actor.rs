@@ -0,0 +1,89 @@
+use tokio::{
+    sync::mpsc::{UnboundedSender, unbounded_channel},
+    task::JoinHandle,
+};
+
+use crate::DynHandler;
+
+enum AsyncDynMessage<M> {
+    Message(M),
+    SwapHandler(Box<dyn DynHandler<M>>),
+}
+
+/// Async counterpart of [`DynActor`](crate::DynActor).
+pub struct AsyncDynActor<M> {
+    sender: UnboundedSender<AsyncDynMessage<M>>,
+    handle: JoinHandle<Box<dyn DynHandler<M>>>,
+}
+
+impl<M> AsyncDynActor<M> {
+    pub async fn send(&self, payload: M) {
+        self.enqueue(AsyncDynMessage::Message(payload))
+    }
+
+    pub async fn swap_handler(&self, handler: Box<dyn DynHandler<M>>) {
+        self.enqueue(AsyncDynMessage::SwapHandler(handler))
+    }
+
+    /// Closes the mailbox, waits for the queued messages to be handled and
+    /// returns the handler that was active last.
+    pub async fn drain(self) -> Box<dyn DynHandler<M>> {
+        drop(self.sender);
+        self.handle.await.unwrap()
+    }
+
+    fn enqueue(&self, message: AsyncDynMessage<M>) {
+        self.sender
+            .send(message)
+            .unwrap_or_else(|_| panic!("task mailbox is closed"))
+    }
+}
+
+pub fn spawn_async_dyn_actor<M>(handler: Box<dyn DynHandler<M>>) -> AsyncDynActor<M>
+where
+    M: Send + 'static,
+{
+    let (sender, mut receiver) = unbounded_channel::<AsyncDynMessage<M>>();
+    let handle = tokio::spawn(async move {
+        let mut handler = handler;
+        while let Some(message) = receiver.recv().await {
+            match message {
+                AsyncDynMessage::Message(payload) => handler.handle(payload),
+                AsyncDynMessage::SwapHandler(next) => handler = next,
+            }
+        }
+        handler
+    });
+
+    AsyncDynActor { sender, handle }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    type Output = Arc<Mutex<Vec<u32>>>;
+
+    fn scaling(factor: u32, output: &Output) -> Box<dyn DynHandler<u32>> {
+        let output = output.clone();
+        Box::new(move |val| output.lock().unwrap().push(val * factor))
+    }
+
+    #[tokio::test]
+    async fn test_async_swap_keeps_pending_messages_in_order() {
+        let output = Output::default();
+        let actor = spawn_async_dyn_actor(scaling(1, &output));
+
+        actor.send(1).await;
+        actor.send(2).await;
+        actor.swap_handler(scaling(10, &output)).await;
+        actor.send(3).await;
+        actor.swap_handler(scaling(100, &output)).await;
+        actor.send(4).await;
+        let mut last = actor.drain().await;
+        last.handle(5);
+
+        assert_eq!(*output.lock().unwrap(), vec![1, 2, 30, 400, 500]);
+    }
+}
@@ -0,0 +1,364 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    hash::Hash,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    sync::{
+        mpsc::{UnboundedReceiver, UnboundedSender, error::SendError, unbounded_channel},
+        watch,
+    },
+    task::JoinHandle,
+};
+
+/// A per-key actor managed by an [`EntityManager`].
+pub trait Entity: Send + Sized + 'static {
+    type Message: Send + 'static;
+
+    fn handle(&mut self, message: Self::Message) -> impl Future<Output = ()> + Send;
+
+    /// Called when the entity is stopped, either because it has been idle
+    /// for too long or because the manager shuts down. This is the place to
+    /// snapshot state that the factory can restore later.
+    fn passivate(self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+}
+
+enum Slot<M> {
+    Active {
+        generation: u64,
+        sender: UnboundedSender<M>,
+        handle: JoinHandle<()>,
+    },
+    Passivating {
+        generation: u64,
+        done: watch::Receiver<bool>,
+    },
+}
+
+impl<M> Slot<M> {
+    /// A panicked entity keeps its slot until the next message for it
+    /// arrives, but its mailbox is closed.
+    fn is_active(&self) -> bool {
+        matches!(self, Slot::Active { sender, .. } if !sender.is_closed())
+    }
+}
+
+struct Entities<K, M> {
+    slots: HashMap<K, Slot<M>>,
+    generation: u64,
+}
+
+type Shared<K, M> = Arc<Mutex<Entities<K, M>>>;
+
+/// Lazily spawns one [`Entity`] per key on its first message and passivates
+/// entities that did not receive a message within the idle timeout.
+///
+/// Messages for an entity that is currently passivating are buffered until
+/// the passivation finished, so a fresh entity never races its predecessor.
+pub struct EntityManager<K, A: Entity> {
+    entities: Shared<K, A::Message>,
+    factory: Arc<dyn Fn(&K) -> A + Send + Sync>,
+    idle_timeout: Duration,
+}
+
+impl<K, A> EntityManager<K, A>
+where
+    K: Hash + Eq + Clone + Send + 'static,
+    A: Entity,
+{
+    pub fn new<F>(idle_timeout: Duration, factory: F) -> Self
+    where
+        F: Fn(&K) -> A + Send + Sync + 'static,
+    {
+        EntityManager {
+            entities: Arc::new(Mutex::new(Entities {
+                slots: HashMap::new(),
+                generation: 0,
+            })),
+            factory: Arc::new(factory),
+            idle_timeout,
+        }
+    }
+
+    pub async fn send(&self, key: K, payload: A::Message) {
+        let mut entities = self.entities.lock().unwrap();
+
+        let mut payload = payload;
+        let mut previous = None;
+        match entities.slots.get(&key) {
+            Some(Slot::Active { sender, .. }) => match sender.send(payload) {
+                Ok(()) => return,
+                // the entity panicked, start over with a fresh one
+                Err(SendError(returned)) => payload = returned,
+            },
+            Some(Slot::Passivating { done, .. }) => previous = Some(done.clone()),
+            None => {}
+        }
+
+        entities.generation += 1;
+        let generation = entities.generation;
+        let (sender, receiver) = unbounded_channel();
+        sender.send(payload).unwrap();
+
+        let handle = tokio::spawn(run_entity(
+            key.clone(),
+            generation,
+            receiver,
+            previous,
+            self.factory.clone(),
+            self.entities.clone(),
+            self.idle_timeout,
+        ));
+
+        entities.slots.insert(
+            key,
+            Slot::Active {
+                generation,
+                sender,
+                handle,
+            },
+        );
+    }
+
+    /// Entities that panicked do not count as active.
+    pub fn is_active(&self, key: &K) -> bool {
+        self.entities
+            .lock()
+            .unwrap()
+            .slots
+            .get(key)
+            .is_some_and(Slot::is_active)
+    }
+
+    pub fn active(&self) -> usize {
+        self.entities
+            .lock()
+            .unwrap()
+            .slots
+            .values()
+            .filter(|slot| slot.is_active())
+            .count()
+    }
+
+    /// Stops all entities after they handled their pending messages and
+    /// waits for them to passivate.
+    pub async fn shutdown(self) {
+        let slots = std::mem::take(&mut self.entities.lock().unwrap().slots);
+
+        let mut handles = Vec::new();
+        let mut passivating = Vec::new();
+        for slot in slots.into_values() {
+            match slot {
+                Slot::Active { sender, handle, .. } => {
+                    drop(sender);
+                    handles.push(handle);
+                }
+                Slot::Passivating { done, .. } => passivating.push(done),
+            }
+        }
+
+        for handle in handles {
+            // an entity that panicked earlier is already gone, the
+            // remaining ones still get passivated
+            let _ = handle.await;
+        }
+        for mut done in passivating {
+            let _ = done.wait_for(|done| *done).await;
+        }
+    }
+}
+
+async fn run_entity<K, A>(
+    key: K,
+    generation: u64,
+    mut receiver: UnboundedReceiver<A::Message>,
+    previous: Option<watch::Receiver<bool>>,
+    factory: Arc<dyn Fn(&K) -> A + Send + Sync>,
+    entities: Shared<K, A::Message>,
+    idle_timeout: Duration,
+) where
+    K: Hash + Eq + Clone + Send + 'static,
+    A: Entity,
+{
+    if let Some(mut previous) = previous {
+        // an error means the previous entity is gone, which is just as good
+        let _ = previous.wait_for(|done| *done).await;
+    }
+
+    let mut entity = factory(&key);
+    loop {
+        match tokio::time::timeout(idle_timeout, receiver.recv()).await {
+            Ok(Some(message)) => entity.handle(message).await,
+            Ok(None) => {
+                entity.passivate().await;
+                return;
+            }
+            Err(_) => {
+                let (done_sender, done) = watch::channel(false);
+                let pending = {
+                    let mut entities = entities.lock().unwrap();
+                    // senders hold the lock, so nothing can sneak in after this check
+                    match receiver.try_recv() {
+                        Ok(message) => Some(message),
+                        Err(_) => {
+                            if owns_slot(&entities, &key, generation) {
+                                entities
+                                    .slots
+                                    .insert(key.clone(), Slot::Passivating { generation, done });
+                            }
+                            None
+                        }
+                    }
+                };
+
+                if let Some(message) = pending {
+                    entity.handle(message).await;
+                    continue;
+                }
+
+                entity.passivate().await;
+                let _ = done_sender.send(true);
+
+                let mut entities = entities.lock().unwrap();
+                if owns_slot(&entities, &key, generation) {
+                    entities.slots.remove(&key);
+                }
+                return;
+            }
+        }
+    }
+}
+
+fn owns_slot<K, M>(entities: &Entities<K, M>, key: &K, generation: u64) -> bool
+where
+    K: Hash + Eq,
+{
+    match entities.slots.get(key) {
+        Some(Slot::Active { generation: g, .. })
+        | Some(Slot::Passivating { generation: g, .. }) => *g == generation,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Snapshots = Arc<Mutex<HashMap<u32, u32>>>;
+
+    struct Counter {
+        id: u32,
+        count: u32,
+        snapshots: Snapshots,
+    }
+
+    impl Entity for Counter {
+        type Message = u32;
+
+        async fn handle(&mut self, message: u32) {
+            assert!(message > 0, "counter {} got an empty increment", self.id);
+            self.count += message;
+        }
+
+        async fn passivate(self) {
+            self.snapshots.lock().unwrap().insert(self.id, self.count);
+        }
+    }
+
+    fn counters(idle_timeout: Duration, snapshots: &Snapshots) -> EntityManager<u32, Counter> {
+        let snapshots = snapshots.clone();
+        EntityManager::new(idle_timeout, move |id: &u32| Counter {
+            id: *id,
+            count: snapshots.lock().unwrap().get(id).copied().unwrap_or(0),
+            snapshots: snapshots.clone(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_entities_are_spawned_lazily_per_key() {
+        let snapshots = Snapshots::default();
+        let manager = counters(Duration::from_secs(60), &snapshots);
+        assert_eq!(manager.active(), 0);
+
+        manager.send(1, 10).await;
+        manager.send(2, 5).await;
+        manager.send(1, 20).await;
+        assert_eq!(manager.active(), 2);
+
+        manager.shutdown().await;
+
+        let snapshots = snapshots.lock().unwrap();
+        assert_eq!(snapshots.get(&1), Some(&30));
+        assert_eq!(snapshots.get(&2), Some(&5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_entities_are_passivated() {
+        let snapshots = Snapshots::default();
+        let manager = counters(Duration::from_millis(20), &snapshots);
+
+        manager.send(1, 10).await;
+        assert!(manager.is_active(&1));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!manager.is_active(&1));
+        assert_eq!(snapshots.lock().unwrap().get(&1), Some(&10));
+
+        manager.shutdown().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_passivated_entity_is_restored_on_next_message() {
+        let snapshots = Snapshots::default();
+        let manager = counters(Duration::from_millis(20), &snapshots);
+
+        manager.send(7, 1).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(manager.active(), 0);
+
+        manager.send(7, 2).await;
+        manager.send(7, 3).await;
+        manager.shutdown().await;
+
+        assert_eq!(snapshots.lock().unwrap().get(&7), Some(&6));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_no_messages_are_lost_around_passivation() {
+        let snapshots = Snapshots::default();
+        let manager = counters(Duration::from_millis(1), &snapshots);
+
+        for i in 0..200 {
+            manager.send(3, 1).await;
+            if i % 20 == 0 {
+                tokio::time::sleep(Duration::from_millis(2)).await;
+            } else {
+                tokio::task::yield_now().await;
+            }
+        }
+        manager.shutdown().await;
+
+        assert_eq!(snapshots.lock().unwrap().get(&3), Some(&200));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_skips_panicked_entities() {
+        let snapshots = Snapshots::default();
+        let manager = counters(Duration::from_secs(60), &snapshots);
+
+        manager.send(1, 0).await;
+        manager.send(2, 5).await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!manager.is_active(&1));
+        assert_eq!(manager.active(), 1);
+        manager.shutdown().await;
+
+        let snapshots = snapshots.lock().unwrap();
+        assert_eq!(snapshots.get(&1), None);
+        assert_eq!(snapshots.get(&2), Some(&5));
+    }
+}
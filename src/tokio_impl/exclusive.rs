@@ -0,0 +1,131 @@
+use std::{future::Future, pin::Pin};
+use tokio::sync::oneshot;
+
+use super::{AsyncTaskSender, spawn_async_task};
+
+type StateFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+type Job<T> = Box<dyn for<'a> FnOnce(&'a mut T) -> StateFuture<'a> + Send>;
+type ReplyingJob<T, R> =
+    Box<dyn for<'a> FnOnce(&'a mut T, oneshot::Sender<R>) -> StateFuture<'a> + Send>;
+
+/// Owns a piece of state in its own task and runs closures against it one
+/// after another, like an Elixir agent.
+///
+/// Handles are cheap to clone. The owner task stops and drops the state
+/// once the last handle is gone.
+pub struct Exclusive<T> {
+    sender: AsyncTaskSender<Job<T>>,
+}
+
+impl<T> Clone for Exclusive<T> {
+    fn clone(&self) -> Self {
+        Exclusive {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> Exclusive<T> {
+    /// Must be called from within a tokio runtime.
+    pub fn new(state: T) -> Self {
+        let task = spawn_async_task(move |mut receiver| async move {
+            let mut state = state;
+            while let Some(job) = receiver.recv().await {
+                let job: Job<T> = job;
+                job(&mut state).await;
+            }
+        });
+
+        Exclusive {
+            sender: task.sender(),
+        }
+    }
+
+    /// Runs `func` against the state once all earlier closures finished.
+    pub async fn with<R, F>(&self, func: F) -> R
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut T) -> R + Send + 'static,
+    {
+        self.run(Box::new(move |state, reply_to| {
+            let _ = reply_to.send(func(state));
+            Box::pin(async {})
+        }))
+        .await
+    }
+
+    /// Like [`Exclusive::with`], but `func` may await while holding the
+    /// state. Nothing else touches the state until its future completes.
+    /// The future has to be boxed, e.g. `|state| Box::pin(async move { .. })`.
+    pub async fn with_async<R, F>(&self, func: F) -> R
+    where
+        R: Send + 'static,
+        F: for<'a> FnOnce(&'a mut T) -> Pin<Box<dyn Future<Output = R> + Send + 'a>>
+            + Send
+            + 'static,
+    {
+        self.run(Box::new(move |state, reply_to| {
+            Box::pin(async move {
+                let _ = reply_to.send(func(state).await);
+            })
+        }))
+        .await
+    }
+
+    async fn run<R: Send + 'static>(&self, job: ReplyingJob<T, R>) -> R {
+        let (reply_to, reply) = oneshot::channel();
+        self.sender
+            .send(Box::new(move |state: &mut T| job(state, reply_to)))
+            .await;
+        reply
+            .await
+            .unwrap_or_else(|_| panic!("exclusive owner task panicked"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_async_closures_run_sequentially() {
+        let counter = Exclusive::new(0u32);
+
+        let mut writers = Vec::new();
+        for _ in 0..10 {
+            let counter = counter.clone();
+            writers.push(tokio::spawn(async move {
+                counter
+                    .with_async(|count| {
+                        Box::pin(async move {
+                            let read = *count;
+                            tokio::time::sleep(Duration::from_millis(1)).await;
+                            *count = read + 1;
+                        })
+                    })
+                    .await
+            }));
+        }
+        for writer in writers {
+            writer.await.unwrap();
+        }
+
+        assert_eq!(counter.with(|count| *count).await, 10);
+    }
+
+    #[tokio::test]
+    async fn test_async_with_returns_the_closure_result() {
+        let names = Exclusive::new(Vec::new());
+
+        names.with(|names| names.push("a")).await;
+        let len = names
+            .with(|names| {
+                names.push("b");
+                names.len()
+            })
+            .await;
+        assert_eq!(len, 2);
+        assert_eq!(names.with(|names| names.join(",")).await, "a,b");
+    }
+}
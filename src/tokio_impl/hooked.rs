@@ -0,0 +1,83 @@
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// Async counterpart of [`HookedReceiver`](crate::HookedReceiver).
+pub struct AsyncHookedReceiver<M, Before, After>
+where
+    After: FnMut(),
+{
+    receiver: UnboundedReceiver<M>,
+    before: Before,
+    after: After,
+    handling: bool,
+}
+
+impl<M, Before, After> AsyncHookedReceiver<M, Before, After>
+where
+    Before: FnMut(&M),
+    After: FnMut(),
+{
+    pub fn new(receiver: UnboundedReceiver<M>, before: Before, after: After) -> Self {
+        AsyncHookedReceiver {
+            receiver,
+            before,
+            after,
+            handling: false,
+        }
+    }
+
+    pub async fn recv(&mut self) -> Option<M> {
+        if std::mem::take(&mut self.handling) {
+            (self.after)();
+        }
+        let payload = self.receiver.recv().await?;
+        (self.before)(&payload);
+        self.handling = true;
+        Some(payload)
+    }
+}
+
+impl<M, Before, After> Drop for AsyncHookedReceiver<M, Before, After>
+where
+    After: FnMut(),
+{
+    fn drop(&mut self) {
+        if self.handling {
+            (self.after)();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_async_task;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn test_async_hooks_run_around_each_message() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let (before, after) = (events.clone(), events.clone());
+        let log = events.clone();
+        let task = spawn_async_task(move |receiver| async move {
+            let mut receiver = AsyncHookedReceiver::new(
+                receiver,
+                move |id: &u32| before.lock().unwrap().push(format!("before {id}")),
+                move || after.lock().unwrap().push("after".to_string()),
+            );
+            while let Some(id) = receiver.recv().await {
+                log.lock().unwrap().push(format!("handle {id}"));
+            }
+        });
+
+        task.send(1).await;
+        task.send(2).await;
+        task.drain().await;
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "before 1", "handle 1", "after", "before 2", "handle 2", "after"
+            ]
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::Received;
+
+/// Async counterpart of [`IdleReceiver`](crate::IdleReceiver).
+pub struct AsyncIdleReceiver<M> {
+    receiver: UnboundedReceiver<M>,
+    idle_timeout: Duration,
+}
+
+impl<M> AsyncIdleReceiver<M> {
+    pub fn new(receiver: UnboundedReceiver<M>, idle_timeout: Duration) -> Self {
+        AsyncIdleReceiver {
+            receiver,
+            idle_timeout,
+        }
+    }
+
+    /// Returns `None` once the mailbox is closed and empty.
+    pub async fn recv(&mut self) -> Option<Received<M>> {
+        match tokio::time::timeout(self.idle_timeout, self.receiver.recv()).await {
+            Ok(payload) => payload.map(Received::Message),
+            Err(_) => Some(Received::Idle),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_async_task;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_async_idle_receiver_reports_idle_mailboxes() {
+        let task = spawn_async_task(|receiver| async move {
+            let mut receiver = AsyncIdleReceiver::new(receiver, Duration::from_secs(30));
+            let mut received = Vec::new();
+            while let Some(event) = receiver.recv().await {
+                let idle = event == Received::Idle;
+                received.push((event, tokio::time::Instant::now()));
+                if idle {
+                    break;
+                }
+            }
+            received
+        });
+
+        let started = tokio::time::Instant::now();
+        task.send(1).await;
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        task.send(2).await;
+
+        let received = task.join().await;
+        assert_eq!(received[0].0, Received::Message(1));
+        assert_eq!(received[1].0, Received::Message(2));
+        assert_eq!(received[2].0, Received::Idle);
+        // 30 seconds after the last message, not after the first
+        assert_eq!(received[2].1 - started, Duration::from_secs(40));
+    }
+}
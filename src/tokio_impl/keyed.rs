@@ -0,0 +1,232 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    future::Future,
+    hash::{Hash, Hasher},
+    pin::Pin,
+};
+use tokio::{
+    sync::{
+        mpsc::{UnboundedSender, unbounded_channel},
+        oneshot,
+    },
+    task::JoinHandle,
+};
+
+enum AsyncRouted<K, M> {
+    Message(K, M),
+    Flush(oneshot::Sender<()>),
+}
+
+struct AsyncWorker<K, M> {
+    sender: UnboundedSender<AsyncRouted<K, M>>,
+    handle: JoinHandle<()>,
+}
+
+type BoxedFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+type AsyncHandler<K, M> = Box<dyn FnMut(K, M) -> BoxedFuture + Send>;
+type AsyncFactory<K, M> = Box<dyn Fn(usize) -> AsyncHandler<K, M> + Send>;
+
+/// Async counterpart of [`KeyedRouter`](crate::KeyedRouter).
+///
+/// Each handler future is awaited before the worker picks up its next
+/// message, so messages with the same key never overlap.
+pub struct AsyncKeyedRouter<K, M> {
+    workers: Vec<AsyncWorker<K, M>>,
+    factory: AsyncFactory<K, M>,
+}
+
+impl<K, M> AsyncKeyedRouter<K, M>
+where
+    K: Hash + Send + 'static,
+    M: Send + 'static,
+{
+    pub fn new<F, H, Fut>(workers: usize, factory: F) -> Self
+    where
+        F: Fn(usize) -> H + Send + 'static,
+        H: FnMut(K, M) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        assert!(workers > 0, "a keyed router needs at least one worker");
+
+        let mut router = AsyncKeyedRouter {
+            workers: Vec::with_capacity(workers),
+            factory: Box::new(move |id| {
+                let mut handler = factory(id);
+                Box::new(move |key, payload| Box::pin(handler(key, payload)))
+            }),
+        };
+        for _ in 0..workers {
+            router.spawn_worker();
+        }
+        router
+    }
+
+    pub async fn send(&self, key: K, payload: M) {
+        let index = self.route(&key);
+        self.workers[index]
+            .sender
+            .send(AsyncRouted::Message(key, payload))
+            .unwrap_or_else(|_| panic!("keyed router worker stopped"))
+    }
+
+    pub fn workers(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub async fn add_worker(&mut self) {
+        self.flush().await;
+        self.spawn_worker();
+    }
+
+    pub async fn remove_worker(&mut self) {
+        assert!(
+            self.workers.len() > 1,
+            "cannot remove the last worker of a keyed router"
+        );
+
+        let worker = self.workers.pop().unwrap();
+        drop(worker.sender);
+        worker.handle.await.unwrap();
+        self.flush().await;
+    }
+
+    pub async fn join(self) {
+        for worker in self.workers {
+            drop(worker.sender);
+            worker.handle.await.unwrap();
+        }
+    }
+
+    fn route(&self, key: &K) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.workers.len() as u64) as usize
+    }
+
+    async fn flush(&self) {
+        let mut acks = Vec::with_capacity(self.workers.len());
+        for worker in &self.workers {
+            let (ack, done) = oneshot::channel();
+            worker
+                .sender
+                .send(AsyncRouted::Flush(ack))
+                .unwrap_or_else(|_| panic!("keyed router worker stopped"));
+            acks.push(done);
+        }
+
+        for done in acks {
+            done.await.unwrap();
+        }
+    }
+
+    fn spawn_worker(&mut self) {
+        let mut handler = (self.factory)(self.workers.len());
+        let (sender, mut receiver) = unbounded_channel::<AsyncRouted<K, M>>();
+        let handle = tokio::spawn(async move {
+            while let Some(routed) = receiver.recv().await {
+                match routed {
+                    AsyncRouted::Message(key, payload) => handler(key, payload).await,
+                    AsyncRouted::Flush(ack) => ack.send(()).unwrap(),
+                }
+            }
+        });
+
+        self.workers.push(AsyncWorker { sender, handle });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    type Log = Arc<Mutex<Vec<(usize, u32, u32)>>>;
+
+    fn logging_router(workers: usize, log: &Log) -> AsyncKeyedRouter<u32, u32> {
+        let log = log.clone();
+        AsyncKeyedRouter::new(workers, move |id| {
+            let log = log.clone();
+            move |key, value| {
+                let log = log.clone();
+                async move {
+                    tokio::task::yield_now().await;
+                    log.lock().unwrap().push((id, key, value));
+                }
+            }
+        })
+    }
+
+    fn assert_in_order_per_key(log: &Log, keys: u32, per_key: u32) {
+        let log = log.lock().unwrap();
+        for key in 0..keys {
+            let values = log
+                .iter()
+                .filter(|(_, k, _)| *k == key)
+                .map(|(_, _, v)| *v)
+                .collect::<Vec<_>>();
+            assert_eq!(values, (0..per_key).collect::<Vec<_>>());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_same_key_is_handled_by_same_worker() {
+        let log = Log::default();
+        let router = logging_router(4, &log);
+
+        for value in 0..10 {
+            for key in 0..8 {
+                router.send(key, value).await;
+            }
+        }
+        router.join().await;
+
+        let log = log.lock().unwrap();
+        for key in 0..8 {
+            let mut workers = log
+                .iter()
+                .filter(|(_, k, _)| *k == key)
+                .map(|(id, _, _)| *id)
+                .collect::<Vec<_>>();
+            workers.dedup();
+            assert_eq!(workers.len(), 1);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_async_messages_per_key_stay_in_order() {
+        let log = Log::default();
+        let router = logging_router(3, &log);
+
+        for value in 0..50 {
+            for key in 0..5 {
+                router.send(key, value).await;
+            }
+        }
+        router.join().await;
+
+        assert_in_order_per_key(&log, 5, 50);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_async_order_survives_adding_and_removing_workers() {
+        let log = Log::default();
+        let mut router = logging_router(2, &log);
+
+        for value in 0..20 {
+            for key in 0..10 {
+                router.send(key, value).await;
+            }
+            if value == 5 {
+                router.add_worker().await;
+                router.add_worker().await;
+            }
+            if value == 12 {
+                router.remove_worker().await;
+            }
+        }
+        assert_eq!(router.workers(), 3);
+        router.join().await;
+
+        assert_in_order_per_key(&log, 10, 20);
+    }
+}
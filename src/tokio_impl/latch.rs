@@ -0,0 +1,87 @@
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Async counterpart of [`crate::Latch`].
+#[derive(Clone)]
+pub struct AsyncLatch {
+    count: Arc<watch::Sender<usize>>,
+}
+
+impl AsyncLatch {
+    pub fn new(count: usize) -> Self {
+        AsyncLatch {
+            count: Arc::new(watch::Sender::new(count)),
+        }
+    }
+
+    pub fn count_down(&self) {
+        self.count.send_if_modified(|count| {
+            if *count == 0 {
+                return false;
+            }
+            *count -= 1;
+            true
+        });
+    }
+
+    pub fn count(&self) -> usize {
+        *self.count.borrow()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.count() == 0
+    }
+
+    pub async fn wait(&self) {
+        let mut count = self.count.subscribe();
+        // the sender lives in `self`, so this cannot fail
+        let _ = count.wait_for(|count| *count == 0).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_async_task;
+    use std::time::Duration;
+    use tokio::sync::mpsc::UnboundedReceiver;
+
+    #[tokio::test]
+    async fn test_async_latch_opens_after_all_workers_are_ready() {
+        let ready = AsyncLatch::new(3);
+
+        let mut workers = Vec::new();
+        for id in 0..3 {
+            let ready = ready.clone();
+            workers.push(spawn_async_task(
+                move |mut receiver: UnboundedReceiver<()>| async move {
+                    ready.count_down();
+                    while receiver.recv().await.is_some() {}
+                    id
+                },
+            ));
+        }
+
+        ready.wait().await;
+        assert!(ready.is_open());
+
+        for worker in workers {
+            worker.drain().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_latch_stays_closed_until_zero() {
+        let ready = AsyncLatch::new(2);
+        ready.count_down();
+
+        let blocked = tokio::time::timeout(Duration::from_millis(10), ready.wait()).await;
+        assert!(blocked.is_err());
+        assert_eq!(ready.count(), 1);
+
+        ready.count_down();
+        ready.count_down();
+        ready.wait().await;
+        assert_eq!(ready.count(), 0);
+    }
+}
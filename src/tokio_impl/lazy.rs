@@ -0,0 +1,184 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
+    task::JoinHandle,
+};
+
+type AsyncBody<M, R> =
+    Box<dyn FnOnce(UnboundedReceiver<M>) -> Pin<Box<dyn Future<Output = R> + Send>> + Send>;
+
+enum AsyncState<M, R> {
+    Pending(AsyncBody<M, R>, UnboundedReceiver<M>),
+    /// The handle is taken out once the task is joined.
+    Running(Option<JoinHandle<R>>),
+}
+
+type AsyncShared<M, R> = Arc<Mutex<AsyncState<M, R>>>;
+
+/// Async counterpart of [`LazyTask`](crate::LazyTask).
+pub struct AsyncLazyTask<M, R> {
+    sender: AsyncLazyTaskSender<M, R>,
+}
+
+impl<M, R> AsyncLazyTask<M, R>
+where
+    M: Send + 'static,
+    R: Send + 'static,
+{
+    pub async fn send(&self, payload: M) {
+        self.sender.send(payload).await
+    }
+
+    /// A handle that starts the task on its first message as well, for
+    /// other tasks to send through before the task is running.
+    pub fn sender(&self) -> AsyncLazyTaskSender<M, R> {
+        self.sender.clone()
+    }
+
+    pub fn is_started(&self) -> bool {
+        matches!(*self.sender.state.lock().unwrap(), AsyncState::Running(_))
+    }
+
+    pub async fn join(self) -> R {
+        let state = self.sender.state.clone();
+        start(&state);
+        Self::handle_of(&state).await.unwrap()
+    }
+
+    pub async fn drain(self) -> R {
+        let state = self.sender.state.clone();
+        start(&state);
+        drop(self.sender);
+        Self::handle_of(&state).await.unwrap()
+    }
+
+    fn handle_of(state: &Mutex<AsyncState<M, R>>) -> JoinHandle<R> {
+        match &mut *state.lock().unwrap() {
+            AsyncState::Running(handle) => handle.take(),
+            AsyncState::Pending(..) => None,
+        }
+        .expect("lazy task is started and joined once")
+    }
+}
+
+/// Async counterpart of [`LazyTaskSender`](crate::LazyTaskSender).
+pub struct AsyncLazyTaskSender<M, R> {
+    sender: UnboundedSender<M>,
+    state: AsyncShared<M, R>,
+}
+
+impl<M, R> Clone for AsyncLazyTaskSender<M, R> {
+    fn clone(&self) -> Self {
+        AsyncLazyTaskSender {
+            sender: self.sender.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<M, R> AsyncLazyTaskSender<M, R>
+where
+    M: Send + 'static,
+    R: Send + 'static,
+{
+    pub async fn send(&self, payload: M) {
+        self.sender
+            .send(payload)
+            .unwrap_or_else(|_| panic!("task mailbox is closed"));
+        start(&self.state);
+    }
+}
+
+fn start<M, R>(state: &Mutex<AsyncState<M, R>>)
+where
+    M: Send + 'static,
+    R: Send + 'static,
+{
+    let mut state = state.lock().unwrap();
+    *state = match std::mem::replace(&mut *state, AsyncState::Running(None)) {
+        AsyncState::Pending(func, receiver) => {
+            AsyncState::Running(Some(tokio::spawn(func(receiver))))
+        }
+        running => running,
+    };
+}
+
+pub fn spawn_async_lazy_task<M, R, Output, Func>(func: Func) -> AsyncLazyTask<M, Output>
+where
+    M: Send + 'static,
+    R: Send + 'static + Future<Output = Output>,
+    Output: Send + 'static,
+    Func: FnOnce(UnboundedReceiver<M>) -> R + Send + 'static,
+{
+    let (sender, receiver) = unbounded_channel::<M>();
+    let body: AsyncBody<M, Output> = Box::new(move |receiver| Box::pin(func(receiver)));
+
+    AsyncLazyTask {
+        sender: AsyncLazyTaskSender {
+            sender,
+            state: Arc::new(Mutex::new(AsyncState::Pending(body, receiver))),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[tokio::test]
+    async fn test_async_lazy_task_starts_on_first_message() {
+        let started = Arc::new(AtomicBool::new(false));
+        let flag = started.clone();
+        let task = spawn_async_lazy_task(move |mut receiver| async move {
+            flag.store(true, Ordering::SeqCst);
+            let mut total = 0;
+            while let Some(val) = receiver.recv().await {
+                total += val;
+            }
+            total
+        });
+
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert!(!task.is_started());
+        assert!(!started.load(Ordering::SeqCst));
+
+        task.send(1).await;
+        assert!(task.is_started());
+        task.send(2).await;
+
+        assert_eq!(task.drain().await, 3);
+        assert!(started.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_async_joining_unstarted_lazy_task_runs_it() {
+        let task = spawn_async_lazy_task(|_receiver: UnboundedReceiver<u32>| async move { 42 });
+
+        assert!(!task.is_started());
+        assert_eq!(task.join().await, 42);
+    }
+
+    #[tokio::test]
+    async fn test_async_lazy_task_sender_starts_the_task() {
+        let task = spawn_async_lazy_task(|mut receiver| async move {
+            let mut total = 0;
+            while let Some(val) = receiver.recv().await {
+                total += val;
+            }
+            total
+        });
+        let sender = task.sender();
+
+        tokio::spawn(async move { sender.send(5u32).await })
+            .await
+            .unwrap();
+        assert!(task.is_started());
+
+        assert_eq!(task.drain().await, 5);
+    }
+}
@@ -0,0 +1,288 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    future::Future,
+    hash::{Hash, Hasher},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+use tokio::{
+    runtime::{Builder, Handle},
+    sync::{
+        mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
+        oneshot,
+    },
+    task::{JoinHandle, LocalSet},
+};
+
+use super::{AsyncMailbox, AsyncTask};
+
+/// Decides on which core a task spawned via [`ThreadPerCore`] runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    RoundRobin,
+    /// Tasks with the same key always end up on the same core.
+    Key(u64),
+    Core(usize),
+}
+
+impl Placement {
+    pub fn for_key<K: Hash + ?Sized>(key: &K) -> Self {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        Placement::Key(hasher.finish())
+    }
+}
+
+/// Spawns a task onto the core's `LocalSet`, on the core thread itself.
+type Spawn = Box<dyn FnOnce() + Send>;
+
+struct Core {
+    handle: Handle,
+    spawns: UnboundedSender<Spawn>,
+    thread: thread::JoinHandle<()>,
+}
+
+/// Aborts the local task once the task driving it is aborted.
+struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Runs one single-threaded tokio runtime per core.
+///
+/// Tasks never migrate between cores, so everything a task touches stays
+/// on one thread. The task's future is created and polled on its core and
+/// does not need to be `Send`, only the closure creating it does. Dropping
+/// the pool stops all cores and drops their tasks.
+///
+/// Tasks on different cores talk through their regular mailboxes, which
+/// are tokio's unbounded mpsc channels rather than dedicated SPSC queues.
+/// With a single producer the mpsc channel sees no contention, and keeping
+/// it means these tasks work with [`AsyncTaskSender`], [`Recipient`] and
+/// everything else built on them.
+///
+/// [`AsyncTaskSender`]: super::AsyncTaskSender
+/// [`Recipient`]: crate::Recipient
+pub struct ThreadPerCore {
+    cores: Vec<Core>,
+    next: AtomicUsize,
+}
+
+impl ThreadPerCore {
+    pub fn new(cores: usize) -> Self {
+        assert!(cores > 0, "thread-per-core mode needs at least one core");
+
+        let cores = (0..cores)
+            .map(|index| {
+                let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+                let handle = runtime.handle().clone();
+                let (spawns, mut spawn_requests) = unbounded_channel::<Spawn>();
+                let thread = thread::Builder::new()
+                    .name(format!("notizia-core-{index}"))
+                    .spawn(move || {
+                        // runs until the pool is dropped
+                        LocalSet::new().block_on(&runtime, async move {
+                            while let Some(spawn) = spawn_requests.recv().await {
+                                spawn();
+                            }
+                        });
+                    })
+                    .unwrap();
+
+                Core {
+                    handle,
+                    spawns,
+                    thread,
+                }
+            })
+            .collect();
+
+        ThreadPerCore {
+            cores,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// One core per available CPU.
+    pub fn available() -> Self {
+        Self::new(thread::available_parallelism().map_or(1, |cores| cores.get()))
+    }
+
+    pub fn cores(&self) -> usize {
+        self.cores.len()
+    }
+
+    pub fn spawn_async_task<M, R, Output, Func>(
+        &self,
+        placement: Placement,
+        func: Func,
+    ) -> AsyncTask<M, Output>
+    where
+        M: Send + 'static,
+        R: 'static + Future<Output = Output>,
+        Output: Send + 'static,
+        Func: FnOnce(UnboundedReceiver<M>) -> R + Send + 'static,
+    {
+        let index = self.core_for(placement);
+        let core = &self.cores[index];
+        let (sender, receiver) = unbounded_channel::<M>();
+        let (spawned, local) = oneshot::channel();
+        let spawn: Spawn = Box::new(move || {
+            let _ = spawned.send(tokio::task::spawn_local(func(receiver)));
+        });
+        core.spawns
+            .send(spawn)
+            .unwrap_or_else(|_| panic!("core {index} stopped"));
+
+        // the `AsyncTask` needs a `Send` handle, so a plain task joins the
+        // local one and forwards its result
+        let handle = core.handle.spawn(async move {
+            let mut local = AbortOnDrop(local.await.expect("core thread stopped"));
+            match (&mut local.0).await {
+                Ok(output) => output,
+                Err(error) if error.is_panic() => std::panic::resume_unwind(error.into_panic()),
+                Err(error) => panic!("{error}"),
+            }
+        });
+
+        AsyncTask {
+            mailbox: AsyncMailbox(sender),
+            handle,
+        }
+    }
+
+    fn core_for(&self, placement: Placement) -> usize {
+        match placement {
+            Placement::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % self.cores.len(),
+            Placement::Key(key) => (key % self.cores.len() as u64) as usize,
+            Placement::Core(index) => {
+                assert!(
+                    index < self.cores.len(),
+                    "core {index} does not exist, there are only {} cores",
+                    self.cores.len()
+                );
+                index
+            }
+        }
+    }
+}
+
+impl Drop for ThreadPerCore {
+    fn drop(&mut self) {
+        for core in self.cores.drain(..) {
+            drop(core.spawns);
+            core.thread.join().unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn current_thread_name() -> String {
+        thread::current().name().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_round_robin_spreads_tasks_over_cores() {
+        let cores = ThreadPerCore::new(3);
+
+        let mut tasks = Vec::new();
+        for _ in 0..6 {
+            tasks.push(cores.spawn_async_task(
+                Placement::RoundRobin,
+                |_receiver: UnboundedReceiver<()>| async move { current_thread_name() },
+            ));
+        }
+
+        let mut names = Vec::new();
+        for task in tasks {
+            names.push(task.join().await);
+        }
+        assert_eq!(
+            names,
+            vec![
+                "notizia-core-0",
+                "notizia-core-1",
+                "notizia-core-2",
+                "notizia-core-0",
+                "notizia-core-1",
+                "notizia-core-2",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_key_and_explicit_placement() {
+        let cores = ThreadPerCore::new(4);
+
+        let first = cores.spawn_async_task(
+            Placement::for_key("user-1"),
+            |_receiver: UnboundedReceiver<()>| async move { current_thread_name() },
+        );
+        let second = cores.spawn_async_task(
+            Placement::for_key("user-1"),
+            |_receiver: UnboundedReceiver<()>| async move { current_thread_name() },
+        );
+        assert_eq!(first.join().await, second.join().await);
+
+        let explicit = cores.spawn_async_task(
+            Placement::Core(2),
+            |_receiver: UnboundedReceiver<()>| async move { current_thread_name() },
+        );
+        assert_eq!(explicit.join().await, "notizia-core-2");
+    }
+
+    #[tokio::test]
+    async fn test_tasks_on_different_cores_exchange_messages() {
+        let cores = ThreadPerCore::new(2);
+
+        let consumer = cores.spawn_async_task(Placement::Core(0), |mut receiver| async move {
+            let mut total = 0;
+            while let Some(val) = receiver.recv().await {
+                total += val;
+            }
+            total
+        });
+
+        let sender = consumer.sender();
+        let producer = cores.spawn_async_task(
+            Placement::Core(1),
+            move |_receiver: UnboundedReceiver<()>| async move {
+                for i in 1..=10 {
+                    sender.send(i).await;
+                }
+            },
+        );
+        producer.join().await;
+
+        assert_eq!(consumer.drain().await, 55);
+    }
+
+    #[tokio::test]
+    async fn test_tasks_may_hold_non_send_state() {
+        let cores = ThreadPerCore::new(2);
+
+        let task = cores.spawn_async_task(Placement::Core(1), |mut receiver| async move {
+            let seen = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+            while let Some(val) = receiver.recv().await {
+                tokio::task::yield_now().await;
+                seen.borrow_mut().push(val);
+            }
+            let seen = seen.borrow().clone();
+            (seen, current_thread_name())
+        });
+
+        for i in 1..=3 {
+            task.send(i).await;
+        }
+        assert_eq!(
+            task.drain().await,
+            (vec![1, 2, 3], "notizia-core-1".to_string())
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use std::{collections::VecDeque, future::Future};
+use tokio::{
+    sync::mpsc::{UnboundedReceiver, UnboundedSender, error::TryRecvError, unbounded_channel},
+    task::JoinHandle,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AsyncLane {
+    High,
+    Normal,
+}
+
+/// Async counterpart of [`PriorityTask`](crate::PriorityTask).
+pub struct AsyncPriorityTask<M, R> {
+    sender: UnboundedSender<(AsyncLane, M)>,
+    handle: JoinHandle<R>,
+}
+
+impl<M, R> AsyncPriorityTask<M, R> {
+    pub async fn send(&self, payload: M) {
+        self.enqueue(AsyncLane::Normal, payload)
+    }
+
+    pub async fn send_priority(&self, payload: M) {
+        self.enqueue(AsyncLane::High, payload)
+    }
+
+    pub async fn join(self) -> R {
+        self.handle.await.unwrap()
+    }
+
+    pub async fn drain(self) -> R {
+        drop(self.sender);
+        self.handle.await.unwrap()
+    }
+
+    fn enqueue(&self, lane: AsyncLane, payload: M) {
+        self.sender
+            .send((lane, payload))
+            .unwrap_or_else(|_| panic!("task mailbox is closed"))
+    }
+}
+
+pub struct AsyncPriorityReceiver<M> {
+    receiver: UnboundedReceiver<(AsyncLane, M)>,
+    high: VecDeque<M>,
+    normal: VecDeque<M>,
+    aging: Option<usize>,
+    overtaken: usize,
+}
+
+impl<M> AsyncPriorityReceiver<M> {
+    /// Prevents a steady stream of high-priority messages from starving the
+    /// normal lane: once `after` high-priority messages overtook the oldest
+    /// waiting normal message, that message is received next.
+    pub fn set_aging(&mut self, after: usize) {
+        assert!(after > 0, "aging needs at least one overtaking message");
+        self.aging = Some(after);
+    }
+
+    /// Waits until a message is available, preferring the high lane.
+    pub async fn recv(&mut self) -> Option<M> {
+        if self.high.is_empty() && self.normal.is_empty() {
+            let (lane, payload) = self.receiver.recv().await?;
+            self.push(lane, payload);
+        }
+        self.collect_pending();
+        self.pop()
+    }
+
+    pub fn try_recv(&mut self) -> Result<M, TryRecvError> {
+        let error = self.collect_pending();
+        self.pop().ok_or(error)
+    }
+
+    /// Moves everything waiting in the channel into the lanes and returns
+    /// why the channel had nothing more.
+    fn collect_pending(&mut self) -> TryRecvError {
+        loop {
+            match self.receiver.try_recv() {
+                Ok((lane, payload)) => self.push(lane, payload),
+                Err(error) => return error,
+            }
+        }
+    }
+
+    fn push(&mut self, lane: AsyncLane, payload: M) {
+        match lane {
+            AsyncLane::High => self.high.push_back(payload),
+            AsyncLane::Normal => self.normal.push_back(payload),
+        }
+    }
+
+    fn pop(&mut self) -> Option<M> {
+        if let Some(after) = self.aging
+            && self.overtaken >= after
+            && let Some(payload) = self.normal.pop_front()
+        {
+            self.overtaken = 0;
+            return Some(payload);
+        }
+
+        match self.high.pop_front() {
+            Some(payload) => {
+                if !self.normal.is_empty() {
+                    self.overtaken += 1;
+                }
+                Some(payload)
+            }
+            None => {
+                self.overtaken = 0;
+                self.normal.pop_front()
+            }
+        }
+    }
+}
+
+pub fn spawn_async_priority_task<M, R, Output, Func>(func: Func) -> AsyncPriorityTask<M, Output>
+where
+    M: Send + 'static,
+    R: Send + 'static + Future<Output = Output>,
+    Output: Send + 'static,
+    Func: FnOnce(AsyncPriorityReceiver<M>) -> R + Send + 'static,
+{
+    let (sender, receiver) = unbounded_channel::<(AsyncLane, M)>();
+    let receiver = AsyncPriorityReceiver {
+        receiver,
+        high: VecDeque::new(),
+        normal: VecDeque::new(),
+        aging: None,
+        overtaken: 0,
+    };
+    let handle = tokio::spawn(func(receiver));
+
+    AsyncPriorityTask { sender, handle }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn test_async_priority_messages_overtake_waiting_messages() {
+        let (start, started) = oneshot::channel::<()>();
+        let task = spawn_async_priority_task(move |mut receiver| async move {
+            started.await.unwrap();
+            let mut received = Vec::new();
+            while let Some(val) = receiver.recv().await {
+                received.push(val);
+            }
+            received
+        });
+
+        task.send(1).await;
+        task.send(2).await;
+        task.send_priority(10).await;
+        task.send(3).await;
+        task.send_priority(11).await;
+        start.send(()).unwrap();
+
+        assert_eq!(task.drain().await, vec![10, 11, 1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_async_lanes_keep_their_own_order() {
+        let (start, started) = oneshot::channel::<()>();
+        let task = spawn_async_priority_task(move |mut receiver| async move {
+            started.await.unwrap();
+            let mut received = Vec::new();
+            for _ in 0..6 {
+                received.push(receiver.recv().await.unwrap());
+            }
+            received
+        });
+
+        for i in 0..3 {
+            task.send(i).await;
+            task.send_priority(100 + i).await;
+        }
+        start.send(()).unwrap();
+
+        assert_eq!(task.join().await, vec![100, 101, 102, 0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_async_aging_lets_normal_messages_through() {
+        let (start, started) = oneshot::channel::<()>();
+        let task = spawn_async_priority_task(move |mut receiver| async move {
+            receiver.set_aging(1);
+            started.await.unwrap();
+            let mut received = Vec::new();
+            while let Some(val) = receiver.recv().await {
+                received.push(val);
+            }
+            received
+        });
+
+        task.send(1).await;
+        task.send(2).await;
+        for i in 0..3 {
+            task.send_priority(100 + i).await;
+        }
+        start.send(()).unwrap();
+
+        assert_eq!(task.drain().await, vec![100, 1, 101, 2, 102]);
+    }
+
+    #[tokio::test]
+    async fn test_async_try_recv_reports_disconnected_once_lanes_are_empty() {
+        let (start, started) = oneshot::channel::<()>();
+        let task = spawn_async_priority_task(move |mut receiver| async move {
+            started.await.unwrap();
+            let mut received = Vec::new();
+            loop {
+                match receiver.try_recv() {
+                    Ok(val) => received.push(val),
+                    Err(TryRecvError::Empty) => tokio::task::yield_now().await,
+                    Err(TryRecvError::Disconnected) => return received,
+                }
+            }
+        });
+
+        task.send(1).await;
+        task.send_priority(2).await;
+        start.send(()).unwrap();
+
+        assert_eq!(task.drain().await, vec![2, 1]);
+    }
+}
@@ -0,0 +1,179 @@
+use std::{io, process::ExitStatus};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::Command,
+    sync::mpsc::{UnboundedReceiver, unbounded_channel},
+};
+
+use super::{AsyncTask, spawn_async_task};
+use crate::Recipient;
+
+/// Frames the messages of a process task: inputs are encoded into bytes
+/// written to stdin, every line read from stdout is decoded into an output.
+pub trait Codec: Send + 'static {
+    type Input: Send + 'static;
+    type Output: Send + 'static;
+
+    fn encode(&mut self, message: Self::Input, buf: &mut Vec<u8>);
+
+    fn decode(&mut self, line: String) -> Self::Output;
+}
+
+/// Passes strings through as lines.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lines;
+
+impl Codec for Lines {
+    type Input = String;
+    type Output = String;
+
+    fn encode(&mut self, message: String, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(message.as_bytes());
+        buf.push(b'\n');
+    }
+
+    fn decode(&mut self, line: String) -> String {
+        line
+    }
+}
+
+/// Runs `command` as a task: messages are encoded onto its stdin, decoded
+/// stdout lines are sent to `output`, usually another task's sender.
+///
+/// Once the task's mailbox is closed, stdin is closed as well and the task
+/// waits for the process to exit. A process that exits on its own ends the
+/// task, even if its mailbox is still open. Either way the task only
+/// finishes after all of stdout has been delivered. The process is killed
+/// if the task is aborted.
+pub fn spawn_process_task<C: Codec>(
+    mut command: Command,
+    mut codec: C,
+    output: impl Into<Recipient<C::Output>>,
+) -> io::Result<AsyncTask<C::Input, io::Result<ExitStatus>>> {
+    let mut child = command
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let mut stdin = child.stdin.take();
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let output = output.into();
+
+    let task = spawn_async_task(
+        move |mut receiver: UnboundedReceiver<C::Input>| async move {
+            let (lines, mut decoded) = unbounded_channel();
+            let reader = tokio::spawn(async move {
+                let mut stdout = BufReader::new(stdout).lines();
+                while let Some(line) = stdout.next_line().await? {
+                    if lines.send(line).is_err() {
+                        break;
+                    }
+                }
+                io::Result::Ok(())
+            });
+
+            let mut buf = Vec::new();
+            let status = loop {
+                tokio::select! {
+                    message = receiver.recv(), if stdin.is_some() => match message {
+                        Some(message) => {
+                            buf.clear();
+                            codec.encode(message, &mut buf);
+                            let pipe = stdin.as_mut().expect("stdin is open");
+                            if pipe.write_all(&buf).await.is_err() || pipe.flush().await.is_err() {
+                                // the process closed stdin, it will tell us more by exiting
+                                stdin = None;
+                            }
+                        }
+                        None => stdin = None,
+                    },
+                    Some(line) = decoded.recv() => {
+                        // nobody listening for output is fine for fire-and-forget processes
+                        let _ = output.try_send(codec.decode(line));
+                    }
+                    status = child.wait() => break status?,
+                }
+            };
+
+            while let Some(line) = decoded.recv().await {
+                let _ = output.try_send(codec.decode(line));
+            }
+            reader.await.expect("stdout reader panicked")?;
+            Ok(status)
+        },
+    );
+
+    Ok(task)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn collector<T: Send + 'static>() -> AsyncTask<T, Vec<T>> {
+        spawn_async_task(|mut receiver: UnboundedReceiver<T>| async move {
+            let mut received = Vec::new();
+            while let Some(output) = receiver.recv().await {
+                received.push(output);
+            }
+            received
+        })
+    }
+
+    #[tokio::test]
+    async fn test_async_process_echoes_messages() {
+        let (output, mut outputs) = unbounded_channel();
+        let task = spawn_process_task(
+            Command::new("cat"),
+            Lines,
+            Recipient::new(move |line| output.send(line).map_err(|_| crate::Disconnected)),
+        )
+        .unwrap();
+
+        task.send("hello".to_string()).await;
+        assert_eq!(outputs.recv().await.unwrap(), "hello");
+        task.send("world".to_string()).await;
+        assert_eq!(outputs.recv().await.unwrap(), "world");
+
+        assert!(task.drain().await.unwrap().success());
+        assert_eq!(outputs.recv().await, None);
+    }
+
+    struct Numbers;
+
+    impl Codec for Numbers {
+        type Input = u32;
+        type Output = Result<u32, String>;
+
+        fn encode(&mut self, message: u32, buf: &mut Vec<u8>) {
+            buf.extend_from_slice(format!("{message}\n").as_bytes());
+        }
+
+        fn decode(&mut self, line: String) -> Result<u32, String> {
+            line.parse().map_err(|_| line)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_process_exiting_on_its_own_ends_the_task() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo 1; echo oops; exit 3"]);
+        let outputs = collector();
+        let task = spawn_process_task(command, Numbers, outputs.sender()).unwrap();
+
+        let status = task.join().await.unwrap();
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(outputs.drain().await, vec![Ok(1), Err("oops".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn test_async_missing_binary_fails_to_spawn() {
+        let outputs = collector();
+        let result = spawn_process_task(
+            Command::new("notizia-does-not-exist"),
+            Lines,
+            outputs.sender(),
+        );
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::NotFound);
+    }
+}
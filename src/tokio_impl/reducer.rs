@@ -0,0 +1,70 @@
+use tokio::sync::watch;
+
+use super::{AsyncTask, spawn_async_task};
+
+/// Async counterpart of [`crate::spawn_reducer`].
+pub fn spawn_async_reducer<M, A, F>(initial: A, mut fold: F) -> AsyncTask<M, A>
+where
+    M: Send + 'static,
+    A: Send + 'static,
+    F: FnMut(A, M) -> A + Send + 'static,
+{
+    spawn_async_task(move |mut receiver| async move {
+        let mut acc = initial;
+        while let Some(message) = receiver.recv().await {
+            acc = fold(acc, message);
+        }
+        acc
+    })
+}
+
+/// Like [`spawn_async_reducer`], but also publishes the accumulator after
+/// every message on the returned watch handle.
+pub fn spawn_async_reducer_with_watch<M, A, F>(
+    initial: A,
+    mut fold: F,
+) -> (AsyncTask<M, A>, watch::Receiver<A>)
+where
+    M: Send + 'static,
+    A: Clone + Send + Sync + 'static,
+    F: FnMut(A, M) -> A + Send + 'static,
+{
+    let (publish, watch) = watch::channel(initial.clone());
+    let task = spawn_async_task(move |mut receiver| async move {
+        let mut acc = initial;
+        while let Some(message) = receiver.recv().await {
+            acc = fold(acc, message);
+            publish.send_replace(acc.clone());
+        }
+        acc
+    });
+    (task, watch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_async_reducer_yields_final_accumulator() {
+        let task = spawn_async_reducer(0, |sum, val: u32| sum + val);
+        for i in 1..=10 {
+            task.send(i).await;
+        }
+        assert_eq!(task.drain().await, 55);
+    }
+
+    #[tokio::test]
+    async fn test_async_reducer_publishes_intermediate_values() {
+        let (task, mut watch) = spawn_async_reducer_with_watch(0, |max, val: u32| max.max(val));
+        assert_eq!(*watch.borrow(), 0);
+
+        task.send(3).await;
+        task.send(7).await;
+        watch.wait_for(|max| *max == 7).await.unwrap();
+
+        task.send(5).await;
+        assert_eq!(task.drain().await, 7);
+        assert_eq!(*watch.borrow(), 7);
+    }
+}
@@ -0,0 +1,68 @@
+use tokio::sync::oneshot;
+
+/// Async counterpart of [`ReplyTo`](crate::ReplyTo). The returned receiver
+/// is a future resolving to the response.
+pub struct AsyncReplyTo<Req, Resp> {
+    pub request: Req,
+    reply_to: oneshot::Sender<Resp>,
+}
+
+impl<Req, Resp> AsyncReplyTo<Req, Resp> {
+    pub fn new(request: Req) -> (Self, oneshot::Receiver<Resp>) {
+        let (reply_to, reply) = oneshot::channel();
+        (AsyncReplyTo { request, reply_to }, reply)
+    }
+
+    /// Hands the response back if the requester stopped waiting.
+    pub fn respond(self, response: Resp) -> Result<(), Resp> {
+        self.reply_to.send(response)
+    }
+
+    pub fn split(self) -> (Req, AsyncReplyTo<(), Resp>) {
+        (
+            self.request,
+            AsyncReplyTo {
+                request: (),
+                reply_to: self.reply_to,
+            },
+        )
+    }
+
+    /// Whether the requester stopped waiting, e.g. to skip expensive work.
+    pub fn is_abandoned(&self) -> bool {
+        self.reply_to.is_closed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_async_task;
+
+    #[tokio::test]
+    async fn test_async_requests_are_answered_through_reply_to() {
+        let task = spawn_async_task(|mut receiver| async move {
+            while let Some(message) = receiver.recv().await {
+                let message: AsyncReplyTo<u32, u32> = message;
+                let val = message.request;
+                let _ = message.respond(val + 1);
+            }
+        });
+
+        let (message, reply) = AsyncReplyTo::new(41);
+        task.sender().send(message).await;
+        assert_eq!(reply.await, Ok(42));
+
+        task.drain().await;
+    }
+
+    #[tokio::test]
+    async fn test_async_abandoned_requests_are_detected() {
+        let (message, reply) = AsyncReplyTo::<(), u32>::new(());
+        assert!(!message.is_abandoned());
+
+        drop(reply);
+        assert!(message.is_abandoned());
+        assert_eq!(message.respond(7), Err(7));
+    }
+}
@@ -0,0 +1,457 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    str::FromStr,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use super::{AsyncTaskSender, spawn_async_task};
+use crate::{Disconnected, Recipient};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronError(String);
+
+impl fmt::Display for CronError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid cron expression: {}", self.0)
+    }
+}
+
+impl std::error::Error for CronError {}
+
+/// A set of allowed values of one cron field, one bit per value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Field(u64);
+
+impl Field {
+    fn parse(text: &str, min: u64, max: u64) -> Result<Field, CronError> {
+        let invalid = || CronError(format!("`{text}` is not within {min}-{max}"));
+        let mut bits = 0;
+
+        for part in text.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, Some(step.parse::<u64>().map_err(|_| invalid())?)),
+                None => (part, None),
+            };
+            let (start, end) = match range {
+                "*" => (min, max),
+                _ => match range.split_once('-') {
+                    Some((start, end)) => (
+                        start.parse().map_err(|_| invalid())?,
+                        end.parse().map_err(|_| invalid())?,
+                    ),
+                    None => {
+                        let value = range.parse().map_err(|_| invalid())?;
+                        // `5/15` means "from 5 on, every 15"
+                        (value, if step.is_some() { max } else { value })
+                    }
+                },
+            };
+            let step = step.unwrap_or(1);
+            if step == 0 || start < min || end > max || start > end {
+                return Err(invalid());
+            }
+            for value in (start..=end).step_by(step as usize) {
+                bits |= 1 << value;
+            }
+        }
+        Ok(Field(bits))
+    }
+
+    fn contains(self, value: u64) -> bool {
+        self.0 & (1 << value) != 0
+    }
+}
+
+/// A parsed cron expression, evaluated in UTC.
+///
+/// Accepts the classic five fields (minute, hour, day of month, month,
+/// day of week) or six with a leading seconds field, each with `*`,
+/// values, ranges, steps and lists. Sunday is day 0 or 7. As in cron, a
+/// day matches if either the day of month or the day of week matches when
+/// both are restricted. `@hourly`, `@daily`, `@weekly`, `@monthly` and
+/// `@yearly` are shorthands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    seconds: Field,
+    minutes: Field,
+    hours: Field,
+    days: Field,
+    months: Field,
+    weekdays: Field,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl FromStr for Cron {
+    type Err = CronError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let expression = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            expression => expression,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let (seconds, rest) = match fields.len() {
+            5 => ("0", &fields[..]),
+            6 => (fields[0], &fields[1..]),
+            count => return Err(CronError(format!("expected 5 or 6 fields, got {count}"))),
+        };
+
+        let mut weekdays = Field::parse(rest[4], 0, 7)?;
+        if weekdays.contains(7) {
+            weekdays.0 = (weekdays.0 | 1) & !(1 << 7);
+        }
+
+        Ok(Cron {
+            seconds: Field::parse(seconds, 0, 59)?,
+            minutes: Field::parse(rest[0], 0, 59)?,
+            hours: Field::parse(rest[1], 0, 23)?,
+            days: Field::parse(rest[2], 1, 31)?,
+            months: Field::parse(rest[3], 1, 12)?,
+            weekdays,
+            any_day: rest[2].starts_with('*'),
+            any_weekday: rest[4].starts_with('*'),
+        })
+    }
+}
+
+impl Cron {
+    /// The first point in time strictly after `after` matching the
+    /// expression, or `None` if it does not match within the next years
+    /// (e.g. for February 30th).
+    pub fn next_after(&self, after: SystemTime) -> Option<SystemTime> {
+        let start = after.duration_since(UNIX_EPOCH).ok()?.as_secs() + 1;
+        let first_day = start / 86_400;
+
+        for day in first_day..first_day + 5 * 366 {
+            let (month, day_of_month) = civil_from_days(day);
+            // 1970-01-01 was a Thursday
+            let weekday = (day + 4) % 7;
+            let from = if day == first_day { start % 86_400 } else { 0 };
+
+            if self.months.contains(month)
+                && self.matches_day(day_of_month, weekday)
+                && let Some(time) = self.time_of_day_from(from)
+            {
+                return Some(UNIX_EPOCH + Duration::from_secs(day * 86_400 + time));
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, day_of_month: u64, weekday: u64) -> bool {
+        match (self.any_day, self.any_weekday) {
+            (false, false) => self.days.contains(day_of_month) || self.weekdays.contains(weekday),
+            (false, true) => self.days.contains(day_of_month),
+            (true, _) => self.weekdays.contains(weekday),
+        }
+    }
+
+    fn time_of_day_from(&self, from: u64) -> Option<u64> {
+        let (from_hour, from_minute, from_second) = (from / 3600, from / 60 % 60, from % 60);
+
+        for hour in (from_hour..24).filter(|hour| self.hours.contains(*hour)) {
+            let first_minute = if hour == from_hour { from_minute } else { 0 };
+            for minute in (first_minute..60).filter(|minute| self.minutes.contains(*minute)) {
+                let first_second = if hour == from_hour && minute == from_minute {
+                    from_second
+                } else {
+                    0
+                };
+                if let Some(second) =
+                    (first_second..60).find(|second| self.seconds.contains(*second))
+                {
+                    return Some(hour * 3600 + minute * 60 + second);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Month and day of month of a day counted from the unix epoch.
+fn civil_from_days(days: u64) -> (u64, u64) {
+    let days = days + 719_468;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    (month, day)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ScheduleId(u64);
+
+struct Entry {
+    cron: Cron,
+    next: SystemTime,
+    fire: Box<dyn FnMut() -> Result<(), Disconnected> + Send>,
+}
+
+enum Command {
+    Schedule(ScheduleId, Entry),
+    Cancel(ScheduleId),
+}
+
+/// The source of wall-clock time for a [`Scheduler`]. Only the scheduler's
+/// own tests replace it; timers still run on tokio's clock.
+pub(crate) trait Clock: Send + Sync + 'static {
+    fn now(&self) -> SystemTime;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// The longest the scheduler sleeps before looking at the clock again, so
+/// that clock steps and system suspends delay a schedule by at most this.
+const RESYNC: Duration = Duration::from_secs(60);
+
+/// Delivers messages according to cron expressions.
+///
+/// Schedules live in memory only and end when the recipient is gone, the
+/// schedule is cancelled or the last handle of the scheduler is dropped.
+///
+/// Cron expressions are matched against the real wall-clock time. Pausing
+/// tokio's clock in a test (`#[tokio::test(start_paused = true)]`) does not
+/// move it, unlike the timeouts and delays elsewhere in the crate.
+#[derive(Clone)]
+pub struct Scheduler {
+    sender: AsyncTaskSender<Command>,
+    next_id: Arc<AtomicU64>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler {
+    /// Must be called from within a tokio runtime.
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock)
+    }
+
+    pub(crate) fn with_clock(clock: impl Clock) -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(clock);
+        let task = spawn_async_task({
+            let clock = clock.clone();
+            move |receiver| run(receiver, clock)
+        });
+        Scheduler {
+            sender: task.sender(),
+            next_id: Arc::new(AtomicU64::new(0)),
+            clock,
+        }
+    }
+
+    /// Sends a clone of `message` to `recipient` whenever `expression`
+    /// matches, e.g. `"0 3 * * *"` for every night at 3am (UTC).
+    pub fn schedule<M, R>(
+        &self,
+        expression: &str,
+        recipient: R,
+        message: M,
+    ) -> Result<ScheduleId, CronError>
+    where
+        M: Clone + Send + 'static,
+        R: Into<Recipient<M>>,
+    {
+        let cron: Cron = expression.parse()?;
+        let next = cron
+            .next_after(self.clock.now())
+            .ok_or_else(|| CronError(format!("`{expression}` never matches")))?;
+
+        let recipient = recipient.into();
+        let id = ScheduleId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let entry = Entry {
+            cron,
+            next,
+            fire: Box::new(move || recipient.try_send(message.clone())),
+        };
+        self.sender.send_from_thread(Command::Schedule(id, entry));
+        Ok(id)
+    }
+
+    pub fn cancel(&self, id: ScheduleId) {
+        self.sender.send_from_thread(Command::Cancel(id));
+    }
+}
+
+async fn run(mut receiver: UnboundedReceiver<Command>, clock: Arc<dyn Clock>) {
+    let mut entries = BTreeMap::<ScheduleId, Entry>::new();
+
+    loop {
+        let command = match entries.values().map(|entry| entry.next).min() {
+            Some(next) => {
+                let wait = next.duration_since(clock.now()).unwrap_or_default();
+                let wait = wait.min(RESYNC);
+                tokio::select! {
+                    command = receiver.recv() => command,
+                    _ = tokio::time::sleep(wait) => {
+                        fire_due(&mut entries, clock.now());
+                        continue;
+                    }
+                }
+            }
+            None => receiver.recv().await,
+        };
+
+        match command {
+            Some(Command::Schedule(id, entry)) => {
+                entries.insert(id, entry);
+            }
+            Some(Command::Cancel(id)) => {
+                entries.remove(&id);
+            }
+            None => return,
+        }
+    }
+}
+
+fn fire_due(entries: &mut BTreeMap<ScheduleId, Entry>, now: SystemTime) {
+    entries.retain(|_, entry| {
+        if entry.next > now {
+            return true;
+        }
+        if (entry.fire)().is_err() {
+            return false;
+        }
+        match entry.cron.next_after(now) {
+            Some(next) => {
+                entry.next = next;
+                true
+            }
+            None => false,
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_async_task;
+    use tokio::time::Instant;
+
+    fn next(expression: &str, after: u64) -> Option<u64> {
+        let cron: Cron = expression.parse().unwrap();
+        cron.next_after(UNIX_EPOCH + Duration::from_secs(after))
+            .map(|next| next.duration_since(UNIX_EPOCH).unwrap().as_secs())
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        const DAY: u64 = 86_400;
+
+        assert_eq!(next("0 3 * * *", 0), Some(3 * 3600));
+        assert_eq!(next("0 3 * * *", 3 * 3600), Some(DAY + 3 * 3600));
+        assert_eq!(next("*/15 * * * *", 60), Some(15 * 60));
+        assert_eq!(next("50/5 * * * *", 0), Some(50 * 60));
+        // `5/1` runs from minute 5 to the end of the hour
+        assert_eq!(next("5/1 * * * *", 5 * 60), Some(6 * 60));
+        assert_eq!(next("30 * * * * *", 0), Some(30));
+        // 1970-01-05 was the first Monday
+        assert_eq!(next("0 0 * * 1", 0), Some(4 * DAY));
+        assert_eq!(next("@monthly", 0), Some(31 * DAY));
+        // 2000-02-29, a leap day
+        assert_eq!(next("0 0 29 2 *", 946_684_800), Some(951_782_400));
+        // either the 13th or a Friday
+        assert_eq!(next("0 0 13 * 5", 0), Some(DAY));
+        assert_eq!(next("0 0 30 2 *", 0), None);
+    }
+
+    #[test]
+    fn test_invalid_cron_expressions() {
+        for expression in [
+            "* * *",
+            "61 * * * *",
+            "* 24 * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+        ] {
+            assert!(expression.parse::<Cron>().is_err(), "{expression}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_scheduler_delivers_messages() {
+        let scheduler = Scheduler::new();
+        let task = spawn_async_task(|mut receiver: UnboundedReceiver<&str>| async move {
+            receiver.recv().await
+        });
+
+        let id = scheduler
+            .schedule("* * * * * *", task.sender(), "tick")
+            .unwrap();
+        assert_eq!(task.join().await, Some("tick"));
+        scheduler.cancel(id);
+
+        assert!(
+            scheduler
+                .schedule("0 0 30 2 *", crate::Recipient::new(|_: ()| Ok(())), ())
+                .is_err()
+        );
+    }
+
+    /// Wall-clock time following tokio's (paused) clock.
+    struct TokioClock {
+        wall: SystemTime,
+        started: Instant,
+    }
+
+    impl Clock for TokioClock {
+        fn now(&self) -> SystemTime {
+            self.wall + self.started.elapsed()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_async_scheduler_follows_an_injected_clock() {
+        let scheduler = Scheduler::with_clock(TokioClock {
+            wall: SystemTime::now(),
+            started: Instant::now(),
+        });
+        let task = spawn_async_task(|mut receiver: UnboundedReceiver<u32>| async move {
+            let mut ticks = 0;
+            while ticks < 3 {
+                ticks += receiver.recv().await.unwrap();
+            }
+            ticks
+        });
+
+        let started = Instant::now();
+        let real = std::time::Instant::now();
+        scheduler
+            .schedule("*/10 * * * * *", task.sender(), 1)
+            .unwrap();
+        assert_eq!(task.join().await, 3);
+
+        // three ticks ten seconds apart, without waiting for them
+        assert!(started.elapsed() >= Duration::from_secs(20));
+        assert!(real.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,457 @@
+use std::fmt;
+
+/// Returned by `call` methods declared with a `#[timeout = ...]` when no
+/// reply arrived in time. The request itself may still be handled later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallTimeout;
+
+impl fmt::Display for CallTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no reply within the call timeout")
+    }
+}
+
+impl std::error::Error for CallTimeout {}
+
+/// Declares a request/response service on top of an async task.
+///
+/// ```ignore
+/// notizia::service! {
+///     pub mod kv {
+///         fn get(key: String) -> Option<u32>;
+///         #[timeout = Duration::from_secs(1)]
+///         call contains(key: String) -> bool;
+///         #[max_in_flight = 64]
+///         cast put(key: String, value: u32);
+///     }
+/// }
+/// ```
+///
+/// generates a module `kv` containing
+/// - `Message`: one variant per method, carrying the arguments, an optional
+///   `reply_to` oneshot sender and an optional backpressure `permit`,
+/// - `Handler`: the server side trait with one async method per request,
+/// - `Client`: a cloneable handle with one async method per request,
+/// - `serve(handler, receiver)`: the dispatch loop, returning the handler
+///   once all clients are gone,
+/// - `spawn(handler)`: spawns `serve` as an [`AsyncTask`](crate::AsyncTask)
+///   and returns it together with a `Client`.
+///
+/// `call` (or `fn`) methods await the handler's reply. With a
+/// `#[timeout = ...]` they return `Result<_, CallTimeout>` instead. `cast`
+/// methods return as soon as the request is queued and ignore the reply.
+/// `#[max_in_flight = n]` limits how many requests of that method the
+/// clients may have queued or in handling at once; further requests wait
+/// for a slot. Both options may be given in either order; the `timeout`
+/// also covers waiting for a slot.
+#[cfg(feature = "macros")]
+#[macro_export]
+macro_rules! service {
+    (
+        $vis:vis mod $name:ident {
+            $(
+                $( #[$attr:ident = $value:expr] )*
+                $kind:ident $method:ident ( $( $arg:ident : $arg_ty:ty ),* $(,)? ) $( -> $ret:ty )? ;
+            )*
+        }
+    ) => {
+        $vis mod $name {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #[allow(non_camel_case_types)]
+            pub enum Message {
+                $(
+                    $method {
+                        $( $arg: $arg_ty, )*
+                        reply_to: ::core::option::Option<
+                            $crate::tokio::sync::oneshot::Sender<$crate::__service_ret!($($ret)?)>,
+                        >,
+                        permit: ::core::option::Option<$crate::tokio::sync::OwnedSemaphorePermit>,
+                    },
+                )*
+            }
+
+            pub trait Handler: Send + 'static {
+                $(
+                    fn $method(
+                        &mut self,
+                        $( $arg: $arg_ty ),*
+                    ) -> impl ::core::future::Future<Output = $crate::__service_ret!($($ret)?)> + Send;
+                )*
+            }
+
+            #[derive(Clone)]
+            pub struct Client {
+                sender: $crate::AsyncTaskSender<Message>,
+                $(
+                    $method: ::core::option::Option<
+                        ::std::sync::Arc<$crate::tokio::sync::Semaphore>,
+                    >,
+                )*
+            }
+
+            impl Client {
+                pub fn new(sender: $crate::AsyncTaskSender<Message>) -> Self {
+                    Client {
+                        sender,
+                        $( $method: $crate::__service_limit!($( $attr = $value ),*), )*
+                    }
+                }
+
+                $(
+                    $crate::__service_method! {
+                        $kind $method ( $( $arg: $arg_ty ),* )
+                            -> $crate::__service_ret!($($ret)?) ; attrs [ $( $attr = $value ),* ]
+                    }
+                )*
+            }
+
+            pub async fn serve<H: Handler>(
+                mut handler: H,
+                mut receiver: $crate::tokio::sync::mpsc::UnboundedReceiver<Message>,
+            ) -> H {
+                while let Some(message) = receiver.recv().await {
+                    match message {
+                        $(
+                            Message::$method { $( $arg, )* reply_to, permit } => {
+                                let reply = handler.$method($( $arg ),*).await;
+                                drop(permit);
+                                if let Some(reply_to) = reply_to {
+                                    // the caller may have given up on the reply
+                                    let _ = reply_to.send(reply);
+                                }
+                            }
+                        )*
+                    }
+                }
+                handler
+            }
+
+            pub fn spawn<H: Handler>(handler: H) -> (Client, $crate::AsyncTask<Message, H>) {
+                let task = $crate::spawn_async_task(move |receiver| serve(handler, receiver));
+                (Client::new(task.sender()), task)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __service_ret {
+    () => {
+        ()
+    };
+    ($ret:ty) => {
+        $ret
+    };
+}
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __service_limit {
+    () => {
+        ::core::option::Option::None
+    };
+    (max_in_flight = $max_in_flight:expr $(, $($rest:tt)*)?) => {
+        ::core::option::Option::Some(::std::sync::Arc::new($crate::tokio::sync::Semaphore::new(
+            $max_in_flight,
+        )))
+    };
+    (timeout = $timeout:expr $(, $($rest:tt)*)?) => {
+        $crate::__service_limit!($($($rest)*)?)
+    };
+    ($attr:ident = $value:expr $(, $($rest:tt)*)?) => {
+        compile_error!(concat!(
+            "unknown service attribute `",
+            stringify!($attr),
+            "`, use `timeout` or `max_in_flight`"
+        ))
+    };
+}
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __service_method {
+    (fn $($rest:tt)*) => {
+        $crate::__service_method! { call $($rest)* }
+    };
+    // picks the timeout out of the method's attributes, in whichever order
+    // they were given
+    ($kind:ident $method:ident $args:tt -> $ret:ty ; attrs [ timeout = $timeout:expr $(, $($rest:tt)*)? ]) => {
+        $crate::__service_method! { $kind $method $args -> $ret ; [ $timeout ] }
+    };
+    ($kind:ident $method:ident $args:tt -> $ret:ty ; attrs [ $attr:ident = $value:expr $(, $($rest:tt)*)? ]) => {
+        $crate::__service_method! { $kind $method $args -> $ret ; attrs [ $($($rest)*)? ] }
+    };
+    ($kind:ident $method:ident $args:tt -> $ret:ty ; attrs []) => {
+        $crate::__service_method! { $kind $method $args -> $ret ; [] }
+    };
+    (call $method:ident ( $( $arg:ident : $arg_ty:ty ),* ) -> $ret:ty ; [] ) => {
+        pub async fn $method(&self, $( $arg: $arg_ty ),*) -> $ret {
+            let permit = $crate::__service_permit!(self.$method);
+            let (reply_to, reply) = $crate::tokio::sync::oneshot::channel();
+            self.sender
+                .send(Message::$method { $( $arg, )* reply_to: Some(reply_to), permit })
+                .await;
+            reply
+                .await
+                .expect(concat!("service stopped before replying to `", stringify!($method), "`"))
+        }
+    };
+    (call $method:ident ( $( $arg:ident : $arg_ty:ty ),* ) -> $ret:ty ; [ $timeout:expr ] ) => {
+        pub async fn $method(
+            &self,
+            $( $arg: $arg_ty ),*
+        ) -> ::core::result::Result<$ret, $crate::CallTimeout> {
+            let call = async move {
+                let permit = $crate::__service_permit!(self.$method);
+                let (reply_to, reply) = $crate::tokio::sync::oneshot::channel();
+                self.sender
+                    .send(Message::$method { $( $arg, )* reply_to: Some(reply_to), permit })
+                    .await;
+                reply.await
+            };
+            match $crate::tokio::time::timeout($timeout, call).await {
+                Ok(reply) => Ok(reply.expect(concat!(
+                    "service stopped before replying to `",
+                    stringify!($method),
+                    "`"
+                ))),
+                Err(_) => Err($crate::CallTimeout),
+            }
+        }
+    };
+    (cast $method:ident ( $( $arg:ident : $arg_ty:ty ),* ) -> $ret:ty ; [] ) => {
+        pub async fn $method(&self, $( $arg: $arg_ty ),*) {
+            let permit = $crate::__service_permit!(self.$method);
+            self.sender
+                .send(Message::$method { $( $arg, )* reply_to: None, permit })
+                .await;
+        }
+    };
+    (cast $method:ident $($rest:tt)*) => {
+        compile_error!(concat!(
+            "cast `",
+            stringify!($method),
+            "` does not wait for a reply and cannot have a timeout"
+        ));
+    };
+    ($kind:ident $method:ident $($rest:tt)*) => {
+        compile_error!(concat!(
+            "`",
+            stringify!($kind),
+            "` is not a method kind, use `fn`, `call` or `cast` for `",
+            stringify!($method),
+            "`"
+        ));
+    };
+}
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __service_permit {
+    ($limit:expr) => {
+        match &$limit {
+            Some(limit) => Some(
+                limit
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("service backpressure semaphore is never closed"),
+            ),
+            None => None,
+        }
+    };
+}
+
+#[cfg(all(test, feature = "macros"))]
+mod tests {
+    use std::{collections::HashMap, sync::Arc, time::Duration};
+    use tokio::sync::Semaphore;
+
+    crate::service! {
+        mod kv {
+            fn get(key: String) -> Option<u32>;
+            fn put(key: String, value: u32) -> ();
+            fn len() -> usize;
+        }
+    }
+
+    crate::service! {
+        mod jobs {
+            cast submit(id: u32);
+            #[max_in_flight = 2]
+            cast submit_limited(id: u32);
+            #[timeout = Duration::from_millis(20)]
+            call slow() -> ();
+            #[timeout = Duration::from_secs(5)]
+            #[max_in_flight = 1]
+            call done() -> Vec<u32>;
+            #[max_in_flight = 1]
+            #[timeout = Duration::from_millis(20)]
+            call gated() -> ();
+        }
+    }
+
+    #[derive(Default)]
+    struct Store(HashMap<String, u32>);
+
+    impl kv::Handler for Store {
+        async fn get(&mut self, key: String) -> Option<u32> {
+            self.0.get(&key).copied()
+        }
+
+        async fn put(&mut self, key: String, value: u32) {
+            self.0.insert(key, value);
+        }
+
+        async fn len(&mut self) -> usize {
+            self.0.len()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_service_client_calls_handler() {
+        let (client, task) = kv::spawn(Store::default());
+
+        client.put("a".to_string(), 1).await;
+        client.put("b".to_string(), 2).await;
+        assert_eq!(client.get("a".to_string()).await, Some(1));
+        assert_eq!(client.get("c".to_string()).await, None);
+        assert_eq!(client.len().await, 2);
+
+        drop(client);
+        let store = task.drain().await;
+        assert_eq!(store.0.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_service_clients_can_be_cloned() {
+        let (client, task) = kv::spawn(Store::default());
+
+        let mut writers = Vec::new();
+        for i in 0..10 {
+            let client = client.clone();
+            writers.push(tokio::spawn(async move {
+                client.put(format!("key-{i}"), i).await;
+            }));
+        }
+        for writer in writers {
+            writer.await.unwrap();
+        }
+
+        assert_eq!(client.len().await, 10);
+        assert_eq!(client.get("key-7".to_string()).await, Some(7));
+
+        drop(client);
+        task.drain().await;
+    }
+
+    struct Jobs {
+        gate: Arc<Semaphore>,
+        done: Vec<u32>,
+    }
+
+    impl jobs::Handler for Jobs {
+        async fn submit(&mut self, id: u32) {
+            self.done.push(id);
+        }
+
+        async fn submit_limited(&mut self, id: u32) {
+            self.gate.acquire().await.unwrap().forget();
+            self.done.push(id);
+        }
+
+        async fn slow(&mut self) {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+
+        async fn done(&mut self) -> Vec<u32> {
+            self.done.clone()
+        }
+
+        async fn gated(&mut self) {
+            self.gate.acquire().await.unwrap().forget();
+        }
+    }
+
+    fn spawn_jobs() -> (
+        jobs::Client,
+        Arc<Semaphore>,
+        crate::AsyncTask<jobs::Message, Jobs>,
+    ) {
+        let gate = Arc::new(Semaphore::new(0));
+        let (client, task) = jobs::spawn(Jobs {
+            gate: gate.clone(),
+            done: Vec::new(),
+        });
+        (client, gate, task)
+    }
+
+    #[tokio::test]
+    async fn test_service_casts_do_not_wait_for_handling() {
+        let (client, _gate, task) = spawn_jobs();
+
+        client.submit(1).await;
+        client.submit(2).await;
+        assert_eq!(client.done().await, Ok(vec![1, 2]));
+
+        drop(client);
+        task.drain().await;
+    }
+
+    #[tokio::test]
+    async fn test_service_call_timeout() {
+        let (client, _gate, task) = spawn_jobs();
+
+        assert_eq!(client.slow().await, Err(crate::CallTimeout));
+        // the timed out request is still handled before later ones
+        assert_eq!(client.done().await, Ok(vec![]));
+
+        drop(client);
+        task.drain().await;
+    }
+
+    #[tokio::test]
+    async fn test_service_max_in_flight_applies_backpressure() {
+        let (client, gate, task) = spawn_jobs();
+
+        client.submit_limited(1).await;
+        client.submit_limited(2).await;
+        let blocked =
+            tokio::time::timeout(Duration::from_millis(20), client.submit_limited(3)).await;
+        assert!(blocked.is_err());
+
+        gate.add_permits(1);
+        tokio::time::timeout(Duration::from_secs(1), client.submit_limited(3))
+            .await
+            .unwrap();
+
+        gate.add_permits(2);
+        assert_eq!(client.done().await, Ok(vec![1, 2, 3]));
+
+        drop(client);
+        task.drain().await;
+    }
+
+    #[tokio::test]
+    async fn test_service_timeout_covers_waiting_for_a_slot() {
+        let (client, gate, task) = spawn_jobs();
+
+        // holds the only slot until the handler passes the gate
+        assert_eq!(client.gated().await, Err(crate::CallTimeout));
+        let waiting = tokio::time::timeout(Duration::from_secs(1), client.gated())
+            .await
+            .expect("waiting for a slot ignored the call timeout");
+        assert_eq!(waiting, Err(crate::CallTimeout));
+
+        gate.add_permits(1);
+        drop(client);
+        task.drain().await;
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    pin::pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    sync::{Notify, mpsc::UnboundedReceiver, watch},
+    task::AbortHandle,
+};
+
+use super::{AsyncTask, spawn_async_task};
+
+#[derive(Default)]
+struct Registry {
+    next_id: u64,
+    live: BTreeMap<u64, (String, Option<AbortHandle>)>,
+}
+
+struct Inner {
+    triggered: watch::Sender<bool>,
+    registry: Mutex<Registry>,
+    finished: Notify,
+}
+
+/// Which tasks had to be aborted because they did not finish within the
+/// grace period.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ShutdownReport {
+    pub aborted: Vec<String>,
+}
+
+impl ShutdownReport {
+    pub fn is_clean(&self) -> bool {
+        self.aborted.is_empty()
+    }
+}
+
+/// Coordinates a graceful shutdown of all tasks spawned through it.
+///
+/// Tasks observe the shutdown via [`Shutdown::triggered`], the owner
+/// broadcasts it with [`Shutdown::trigger`] and collects stragglers with
+/// [`Shutdown::wait`].
+#[derive(Clone)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+struct Registration {
+    id: u64,
+    inner: Arc<Inner>,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.inner.registry.lock().unwrap().live.remove(&self.id);
+        self.inner.finished.notify_waiters();
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Shutdown {
+            inner: Arc::new(Inner {
+                triggered: watch::Sender::new(false),
+                registry: Mutex::new(Registry::default()),
+                finished: Notify::new(),
+            }),
+        }
+    }
+
+    /// Spawns a task like [`spawn_async_task`] and registers it under the
+    /// given name.
+    pub fn spawn_async_task<M, R, Output, Func>(
+        &self,
+        name: impl Into<String>,
+        func: Func,
+    ) -> AsyncTask<M, Output>
+    where
+        M: Send + 'static,
+        R: Send + 'static + Future<Output = Output>,
+        Output: Send + 'static,
+        Func: FnOnce(UnboundedReceiver<M>) -> R + Send + 'static,
+    {
+        let id = {
+            let mut registry = self.inner.registry.lock().unwrap();
+            let id = registry.next_id;
+            registry.next_id += 1;
+            registry.live.insert(id, (name.into(), None));
+            id
+        };
+        let registration = Registration {
+            id,
+            inner: self.inner.clone(),
+        };
+
+        let task = spawn_async_task(move |receiver| {
+            let future = func(receiver);
+            async move {
+                let _registration = registration;
+                future.await
+            }
+        });
+
+        if let Some((_, abort)) = self.inner.registry.lock().unwrap().live.get_mut(&id) {
+            *abort = Some(task.handle.abort_handle());
+        }
+        task
+    }
+
+    pub fn trigger(&self) {
+        self.inner.triggered.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.inner.triggered.borrow()
+    }
+
+    /// Resolves once the shutdown has been triggered.
+    pub async fn triggered(&self) {
+        let mut triggered = self.inner.triggered.subscribe();
+        // the sender lives in `self`, so this cannot fail
+        let _ = triggered.wait_for(|triggered| *triggered).await;
+    }
+
+    pub fn live(&self) -> Vec<String> {
+        self.inner
+            .registry
+            .lock()
+            .unwrap()
+            .live
+            .values()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Triggers the shutdown (if not done already), waits up to `grace` for
+    /// all registered tasks to finish and aborts the remaining ones.
+    pub async fn wait(&self, grace: Duration) -> ShutdownReport {
+        self.trigger();
+
+        let deadline = tokio::time::Instant::now() + grace;
+        loop {
+            let mut finished = pin!(self.inner.finished.notified());
+            finished.as_mut().enable();
+
+            if self.inner.registry.lock().unwrap().live.is_empty() {
+                return ShutdownReport::default();
+            }
+            if tokio::time::timeout_at(deadline, finished).await.is_err() {
+                break;
+            }
+        }
+
+        let stragglers = std::mem::take(&mut self.inner.registry.lock().unwrap().live);
+        let aborted = stragglers
+            .into_values()
+            .map(|(name, abort)| {
+                if let Some(abort) = abort {
+                    abort.abort();
+                }
+                name
+            })
+            .collect();
+
+        ShutdownReport { aborted }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tasks_finish_within_grace_period() {
+        let shutdown = Shutdown::new();
+
+        let listener = shutdown.clone();
+        let task = shutdown.spawn_async_task(
+            "worker",
+            move |_receiver: UnboundedReceiver<()>| async move {
+                listener.triggered().await;
+                42
+            },
+        );
+        assert_eq!(shutdown.live(), vec!["worker".to_string()]);
+
+        let report = shutdown.wait(Duration::from_secs(1)).await;
+        assert!(report.is_clean());
+        assert!(shutdown.live().is_empty());
+        assert_eq!(task.join().await, 42);
+    }
+
+    #[tokio::test]
+    async fn test_stragglers_are_aborted_and_reported() {
+        let shutdown = Shutdown::new();
+
+        let listener = shutdown.clone();
+        let _polite = shutdown.spawn_async_task(
+            "polite",
+            move |_receiver: UnboundedReceiver<()>| async move { listener.triggered().await },
+        );
+        let stubborn =
+            shutdown.spawn_async_task("stubborn", |_receiver: UnboundedReceiver<()>| async move {
+                tokio::time::sleep(Duration::from_secs(60)).await
+            });
+
+        let report = shutdown.wait(Duration::from_millis(50)).await;
+        assert_eq!(report.aborted, vec!["stubborn".to_string()]);
+
+        assert!(stubborn.handle.await.unwrap_err().is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_finished_tasks_are_unregistered() {
+        let shutdown = Shutdown::new();
+
+        let task = shutdown.spawn_async_task("short", |mut receiver| async move {
+            receiver.recv().await.unwrap()
+        });
+        task.send(7).await;
+        assert_eq!(task.join().await, 7);
+
+        assert!(shutdown.live().is_empty());
+        assert!(!shutdown.is_triggered());
+        assert!(shutdown.wait(Duration::from_millis(10)).await.is_clean());
+        assert!(shutdown.is_triggered());
+    }
+}
@@ -0,0 +1,123 @@
+use std::io;
+use tokio::task::JoinHandle;
+
+use super::AsyncTask;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Interrupt,
+    Terminate,
+    Hangup,
+}
+
+/// Keeps forwarding signals until [`SignalSubscription::unsubscribe`] is
+/// called or the subscribed task stopped receiving.
+#[must_use = "dropping the subscription keeps it running, call `unsubscribe` to stop it"]
+pub struct SignalSubscription {
+    handle: JoinHandle<()>,
+}
+
+impl SignalSubscription {
+    /// Stops forwarding. The signal handlers stay installed for the rest of
+    /// the process, so the subscribed signals no longer get their default
+    /// behaviour (e.g. SIGTERM does not terminate the process anymore).
+    pub fn unsubscribe(self) {
+        self.handle.abort();
+    }
+}
+
+/// Delivers the given OS `signals` into the mailbox of `task`, converted via
+/// `map`.
+///
+/// The signal handlers are installed before this function returns, so a
+/// signal raised right afterwards is not missed. They are never uninstalled:
+/// once subscribed, a signal is swallowed for the rest of the process, even
+/// after [`SignalSubscription::unsubscribe`] or once the task stopped. Only
+/// subscribe to the signals you handle. Must be called from within a tokio
+/// runtime.
+#[cfg(unix)]
+pub fn subscribe<M, R, F>(
+    task: &AsyncTask<M, R>,
+    signals: &[Signal],
+    map: F,
+) -> io::Result<SignalSubscription>
+where
+    M: Send + 'static,
+    F: Fn(Signal) -> M + Send + 'static,
+{
+    use std::task::Poll;
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut streams = Vec::new();
+    for &kind in signals {
+        if streams.iter().any(|(subscribed, _)| *subscribed == kind) {
+            continue;
+        }
+        let os_kind = match kind {
+            Signal::Interrupt => SignalKind::interrupt(),
+            Signal::Terminate => SignalKind::terminate(),
+            Signal::Hangup => SignalKind::hangup(),
+        };
+        streams.push((kind, signal(os_kind)?));
+    }
+    let sender = task.mailbox.0.clone();
+
+    let handle = tokio::spawn(async move {
+        loop {
+            let received = std::future::poll_fn(|cx| {
+                for (kind, stream) in streams.iter_mut() {
+                    match stream.poll_recv(cx) {
+                        Poll::Ready(Some(())) => return Poll::Ready(Some(*kind)),
+                        Poll::Ready(None) => return Poll::Ready(None),
+                        Poll::Pending => {}
+                    }
+                }
+                Poll::Pending
+            })
+            .await;
+            let Some(received) = received else {
+                return;
+            };
+            if sender.send(map(received)).is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(SignalSubscription { handle })
+}
+
+/// Delivers Ctrl-C into the mailbox of `task`, converted via `map`.
+///
+/// Only [`Signal::Interrupt`] is available on this platform, any other
+/// signal in `signals` fails with [`io::ErrorKind::Unsupported`]. The
+/// handler stays installed for the rest of the process.
+#[cfg(not(unix))]
+pub fn subscribe<M, R, F>(
+    task: &AsyncTask<M, R>,
+    signals: &[Signal],
+    map: F,
+) -> io::Result<SignalSubscription>
+where
+    M: Send + 'static,
+    F: Fn(Signal) -> M + Send + 'static,
+{
+    if signals.iter().any(|kind| *kind != Signal::Interrupt) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "only Signal::Interrupt is available on this platform",
+        ));
+    }
+    let interrupt = !signals.is_empty();
+    let sender = task.mailbox.0.clone();
+
+    let handle = tokio::spawn(async move {
+        while interrupt && tokio::signal::ctrl_c().await.is_ok() {
+            if sender.send(map(Signal::Interrupt)).is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(SignalSubscription { handle })
+}
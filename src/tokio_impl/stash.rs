@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+use tokio::sync::mpsc::{UnboundedReceiver, error::TryRecvError};
+
+/// Async counterpart of [`StashReceiver`](crate::StashReceiver).
+pub struct AsyncStashReceiver<M> {
+    receiver: UnboundedReceiver<M>,
+    stash: VecDeque<M>,
+    replay: VecDeque<M>,
+}
+
+impl<M> AsyncStashReceiver<M> {
+    pub fn new(receiver: UnboundedReceiver<M>) -> Self {
+        AsyncStashReceiver {
+            receiver,
+            stash: VecDeque::new(),
+            replay: VecDeque::new(),
+        }
+    }
+
+    /// Waits until a message is available, unstashed messages first.
+    pub async fn recv(&mut self) -> Option<M> {
+        match self.replay.pop_front() {
+            Some(payload) => Some(payload),
+            None => self.receiver.recv().await,
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Result<M, TryRecvError> {
+        match self.replay.pop_front() {
+            Some(payload) => Ok(payload),
+            None => self.receiver.try_recv(),
+        }
+    }
+
+    pub fn stash(&mut self, payload: M) {
+        self.stash.push_back(payload);
+    }
+
+    pub fn stashed(&self) -> usize {
+        self.stash.len() + self.replay.len()
+    }
+
+    pub fn unstash_all(&mut self) {
+        let mut stash = std::mem::take(&mut self.stash);
+        stash.append(&mut self.replay);
+        self.replay = stash;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spawn_async_task;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Message {
+        Ready,
+        Work(u32),
+    }
+
+    #[tokio::test]
+    async fn test_async_stashed_messages_are_replayed_once_ready() {
+        let task = spawn_async_task(|receiver| async move {
+            let mut receiver = AsyncStashReceiver::new(receiver);
+            while let Some(message) = receiver.recv().await {
+                match message {
+                    Message::Ready => break,
+                    work => receiver.stash(work),
+                }
+            }
+            receiver.unstash_all();
+
+            let mut handled = Vec::new();
+            while let Some(message) = receiver.recv().await {
+                handled.push(message);
+            }
+            handled
+        });
+
+        task.send(Message::Work(1)).await;
+        task.send(Message::Work(2)).await;
+        task.send(Message::Ready).await;
+        task.send(Message::Work(3)).await;
+
+        assert_eq!(
+            task.drain().await,
+            vec![Message::Work(1), Message::Work(2), Message::Work(3)]
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use std::{future::Future, time::Duration};
+use tokio::{
+    sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
+    task::JoinHandle,
+    time::Instant,
+};
+
+use crate::QueueLatency;
+
+/// Async counterpart of [`TimedTask`](crate::TimedTask). Messages are
+/// stamped with tokio's clock, so paused time applies.
+pub struct AsyncTimedTask<M, R> {
+    sender: AsyncTimedSender<M>,
+    handle: JoinHandle<R>,
+}
+
+impl<M, R> AsyncTimedTask<M, R> {
+    pub async fn send(&self, payload: M) {
+        self.sender.send(payload).await
+    }
+
+    pub fn sender(&self) -> AsyncTimedSender<M> {
+        self.sender.clone()
+    }
+
+    pub async fn join(self) -> R {
+        self.handle.await.unwrap()
+    }
+
+    pub async fn drain(self) -> R {
+        drop(self.sender);
+        self.handle.await.unwrap()
+    }
+}
+
+pub struct AsyncTimedSender<M>(UnboundedSender<(Instant, M)>);
+
+impl<M> Clone for AsyncTimedSender<M> {
+    fn clone(&self) -> Self {
+        AsyncTimedSender(self.0.clone())
+    }
+}
+
+impl<M> AsyncTimedSender<M> {
+    pub async fn send(&self, payload: M) {
+        self.0
+            .send((Instant::now(), payload))
+            .unwrap_or_else(|_| panic!("task mailbox is closed"))
+    }
+}
+
+pub struct AsyncTimedReceiver<M> {
+    receiver: UnboundedReceiver<(Instant, M)>,
+    sent_at: Option<Instant>,
+    latency: QueueLatency,
+}
+
+impl<M> AsyncTimedReceiver<M> {
+    pub async fn recv(&mut self) -> Option<M> {
+        let (sent_at, payload) = self.receiver.recv().await?;
+        self.latency.record(sent_at.elapsed());
+        self.sent_at = Some(sent_at);
+        Some(payload)
+    }
+
+    pub fn message_age(&self) -> Option<Duration> {
+        self.sent_at.map(|sent_at| sent_at.elapsed())
+    }
+
+    pub fn latency(&self) -> &QueueLatency {
+        &self.latency
+    }
+}
+
+pub fn spawn_async_timed_task<M, R, Output, Func>(func: Func) -> AsyncTimedTask<M, Output>
+where
+    M: Send + 'static,
+    R: Send + 'static + Future<Output = Output>,
+    Output: Send + 'static,
+    Func: FnOnce(AsyncTimedReceiver<M>) -> R + Send + 'static,
+{
+    let (sender, receiver) = unbounded_channel::<(Instant, M)>();
+    let receiver = AsyncTimedReceiver {
+        receiver,
+        sent_at: None,
+        latency: QueueLatency::default(),
+    };
+    let handle = tokio::spawn(func(receiver));
+
+    AsyncTimedTask {
+        sender: AsyncTimedSender(sender),
+        handle,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_async_timed_receiver_separates_queueing_from_handling() {
+        let (start, started) = oneshot::channel::<()>();
+        let task = spawn_async_timed_task(move |mut receiver| async move {
+            started.await.unwrap();
+            let mut ages = Vec::new();
+            while receiver.recv().await.is_some() {
+                let queued = receiver.message_age().unwrap();
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                ages.push((queued, receiver.message_age().unwrap()));
+            }
+            (ages, receiver.latency().percentile(100.0))
+        });
+
+        task.send(1).await;
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        start.send(()).unwrap();
+
+        let (ages, slowest) = task.drain().await;
+        assert_eq!(ages, vec![(Duration::from_secs(5), Duration::from_secs(6))]);
+        assert_eq!(slowest, Some(Duration::from_secs(5)));
+    }
+}
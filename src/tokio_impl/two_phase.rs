@@ -0,0 +1,211 @@
+use std::{collections::HashMap, time::Duration};
+use tokio::time::{Instant, timeout_at};
+
+use super::AsyncReplyTo;
+use crate::Recipient;
+
+pub type TxId = u64;
+
+/// The participant side of a [`Coordinator`] transaction. A participant
+/// votes on `Prepare` after staging the operation, then applies or drops
+/// the staged operation on `Commit` or `Abort`. Unknown ids are ignored.
+pub enum TwoPhase<T> {
+    Prepare(TxId, AsyncReplyTo<T, Vote>),
+    Commit(TxId),
+    Abort(TxId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vote {
+    Yes,
+    No,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Committed,
+    Aborted,
+}
+
+/// Where a [`Coordinator`] records its decisions before telling the
+/// participants. Implement it on durable storage to recover decisions
+/// after a coordinator crash.
+pub trait DecisionLog: Send {
+    fn record(&mut self, tx: TxId, decision: Decision);
+
+    fn decision(&self, tx: TxId) -> Option<Decision>;
+}
+
+/// The default [`DecisionLog`]. Kept in memory only, so decisions are lost
+/// if the process dies between recording and delivering them.
+#[derive(Debug, Default)]
+pub struct MemoryLog {
+    decisions: HashMap<TxId, Decision>,
+}
+
+impl DecisionLog for MemoryLog {
+    fn record(&mut self, tx: TxId, decision: Decision) {
+        self.decisions.insert(tx, decision);
+    }
+
+    fn decision(&self, tx: TxId) -> Option<Decision> {
+        self.decisions.get(&tx).copied()
+    }
+}
+
+/// Runs operations that must touch several tasks atomically through a
+/// two-phase commit: every participant is asked to prepare, and only if
+/// all vote [`Vote::Yes`] within the prepare timeout are they told to
+/// commit. A `No`, a timeout or a gone participant aborts.
+pub struct Coordinator<T, L = MemoryLog> {
+    participants: Vec<Recipient<TwoPhase<T>>>,
+    prepare_timeout: Duration,
+    log: L,
+    next_tx: TxId,
+}
+
+impl<T> Coordinator<T> {
+    pub fn new(prepare_timeout: Duration) -> Self {
+        Coordinator::with_log(prepare_timeout, MemoryLog::default())
+    }
+}
+
+impl<T, L> Coordinator<T, L> {
+    pub fn with_log(prepare_timeout: Duration, log: L) -> Self {
+        Coordinator {
+            participants: Vec::new(),
+            prepare_timeout,
+            log,
+            next_tx: 0,
+        }
+    }
+
+    pub fn add_participant(&mut self, participant: impl Into<Recipient<TwoPhase<T>>>) {
+        self.participants.push(participant.into());
+    }
+
+    pub fn log(&self) -> &L {
+        &self.log
+    }
+}
+
+impl<T: Clone, L: DecisionLog> Coordinator<T, L> {
+    /// Sends `operation` to every participant and returns the decision,
+    /// which is recorded in the log before any participant learns it.
+    pub async fn run(&mut self, operation: T) -> (TxId, Decision) {
+        let tx = self.next_tx;
+        self.next_tx += 1;
+
+        let deadline = Instant::now() + self.prepare_timeout;
+        let mut votes = Vec::with_capacity(self.participants.len());
+        let mut all_sent = true;
+        for participant in &self.participants {
+            let (prepare, vote) = AsyncReplyTo::new(operation.clone());
+            all_sent &= participant.try_send(TwoPhase::Prepare(tx, prepare)).is_ok();
+            votes.push(vote);
+        }
+
+        let mut decision = if all_sent {
+            Decision::Committed
+        } else {
+            Decision::Aborted
+        };
+        for vote in votes {
+            if decision == Decision::Aborted {
+                break;
+            }
+            if !matches!(timeout_at(deadline, vote).await, Ok(Ok(Vote::Yes))) {
+                decision = Decision::Aborted;
+            }
+        }
+
+        self.log.record(tx, decision);
+        for participant in &self.participants {
+            let message = match decision {
+                Decision::Committed => TwoPhase::Commit(tx),
+                Decision::Aborted => TwoPhase::Abort(tx),
+            };
+            let _ = participant.try_send(message);
+        }
+        (tx, decision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AsyncTask, spawn_async_task};
+
+    /// Moves `amount` out of (negative) or into its balance, voting no if
+    /// that would overdraw it. Holds the vote when `stall` is set.
+    fn account(balance: i64, stall: bool) -> AsyncTask<TwoPhase<i64>, i64> {
+        spawn_async_task(move |mut receiver| async move {
+            let mut balance = balance;
+            let mut staged = HashMap::new();
+            let mut held = Vec::new();
+            while let Some(message) = receiver.recv().await {
+                match message {
+                    TwoPhase::Prepare(_, prepare) if stall => held.push(prepare),
+                    TwoPhase::Prepare(tx, prepare) => {
+                        let amount = prepare.request;
+                        if balance + amount < 0 {
+                            let _ = prepare.respond(Vote::No);
+                        } else {
+                            staged.insert(tx, amount);
+                            let _ = prepare.respond(Vote::Yes);
+                        }
+                    }
+                    TwoPhase::Commit(tx) => balance += staged.remove(&tx).unwrap_or(0),
+                    TwoPhase::Abort(tx) => {
+                        staged.remove(&tx);
+                    }
+                }
+            }
+            balance
+        })
+    }
+
+    #[tokio::test]
+    async fn test_async_coordinator_commits_when_all_vote_yes() {
+        let (a, b) = (account(10, false), account(-5, false));
+        let mut coordinator = Coordinator::new(Duration::from_secs(1));
+        coordinator.add_participant(a.sender());
+        coordinator.add_participant(b.sender());
+
+        assert_eq!(coordinator.run(5).await, (0, Decision::Committed));
+        assert_eq!(coordinator.log().decision(0), Some(Decision::Committed));
+
+        drop(coordinator);
+        assert_eq!((a.drain().await, b.drain().await), (15, 0));
+    }
+
+    #[tokio::test]
+    async fn test_async_coordinator_aborts_on_a_no_vote() {
+        let (a, b) = (account(10, false), account(3, false));
+        let mut coordinator = Coordinator::new(Duration::from_secs(1));
+        coordinator.add_participant(a.sender());
+        coordinator.add_participant(b.sender());
+
+        assert_eq!(coordinator.run(-5).await, (0, Decision::Aborted));
+        assert_eq!(coordinator.run(-3).await, (1, Decision::Committed));
+
+        drop(coordinator);
+        assert_eq!((a.drain().await, b.drain().await), (7, 0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_async_coordinator_aborts_when_prepare_times_out() {
+        let (a, b) = (account(10, false), account(10, true));
+        let mut coordinator = Coordinator::new(Duration::from_secs(1));
+        coordinator.add_participant(a.sender());
+        coordinator.add_participant(b.sender());
+
+        let started = Instant::now();
+        assert_eq!(coordinator.run(5).await, (0, Decision::Aborted));
+        assert_eq!(started.elapsed(), Duration::from_secs(1));
+        assert_eq!(coordinator.log().decision(0), Some(Decision::Aborted));
+
+        drop(coordinator);
+        assert_eq!((a.drain().await, b.drain().await), (10, 10));
+    }
+}
@@ -0,0 +1,330 @@
+use std::{
+    sync::{
+        Arc, Mutex, Weak,
+        atomic::{AtomicU64, Ordering},
+        mpsc::{Receiver, RecvError, Sender, TryRecvError, channel},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "tokio")]
+use crate::Recipient;
+
+/// Reported by [`Watchdog::stalled`] for a task that has messages waiting
+/// but did not receive any of them for the watchdog's threshold, likely
+/// because it is stuck in a handler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stall {
+    pub name: String,
+    pub pending: u64,
+    /// How long the task has not made progress.
+    pub since: Duration,
+}
+
+struct Progress {
+    name: String,
+    sent: AtomicU64,
+    received: AtomicU64,
+    last_progress: Mutex<Instant>,
+}
+
+impl Progress {
+    /// Counts a message before it is put into the mailbox, so `received`
+    /// never overtakes `sent`.
+    fn sending(&self) {
+        if self.sent.fetch_add(1, Ordering::SeqCst) == self.received.load(Ordering::SeqCst) {
+            // the mailbox was empty, waiting starts now
+            *self.last_progress.lock().unwrap() = Instant::now();
+        }
+    }
+
+    fn received(&self) {
+        self.received.fetch_add(1, Ordering::SeqCst);
+        *self.last_progress.lock().unwrap() = Instant::now();
+    }
+
+    fn stall(&self, threshold: Duration) -> Option<Stall> {
+        let pending = self
+            .sent
+            .load(Ordering::SeqCst)
+            .saturating_sub(self.received.load(Ordering::SeqCst));
+        let since = self.last_progress.lock().unwrap().elapsed();
+        (pending > 0 && since >= threshold).then(|| Stall {
+            name: self.name.clone(),
+            pending,
+            since,
+        })
+    }
+}
+
+type Watched = Arc<Mutex<Vec<Weak<Progress>>>>;
+
+/// Opt-in diagnostics for tasks that stopped receiving: tasks spawned
+/// through the watchdog count their sent and received messages, and
+/// [`Watchdog::stalled`] lists those with pending messages and no receive
+/// for `stall_after`.
+///
+/// Progress is measured in real time, also in tests that pause tokio's
+/// clock.
+#[derive(Clone)]
+pub struct Watchdog {
+    stall_after: Duration,
+    tasks: Watched,
+}
+
+impl Watchdog {
+    pub fn new(stall_after: Duration) -> Self {
+        Watchdog {
+            stall_after,
+            tasks: Arc::default(),
+        }
+    }
+
+    pub fn spawn_task<M, R, Func>(&self, name: impl Into<String>, func: Func) -> WatchedTask<M, R>
+    where
+        M: Send + 'static,
+        R: Send + 'static,
+        Func: FnOnce(WatchedReceiver<M>) -> R + Send + 'static,
+    {
+        let progress = self.watch(name.into());
+        let (sender, receiver) = channel::<M>();
+        let receiver = WatchedReceiver {
+            receiver,
+            progress: progress.clone(),
+        };
+        let handle = std::thread::spawn(move || func(receiver));
+
+        WatchedTask {
+            sender,
+            progress,
+            handle,
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    pub fn spawn_async_task<M, R, Output, Func>(
+        &self,
+        name: impl Into<String>,
+        func: Func,
+    ) -> AsyncWatchedTask<M, Output>
+    where
+        M: Send + 'static,
+        R: Send + 'static + std::future::Future<Output = Output>,
+        Output: Send + 'static,
+        Func: FnOnce(AsyncWatchedReceiver<M>) -> R + Send + 'static,
+    {
+        let progress = self.watch(name.into());
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel::<M>();
+        let receiver = AsyncWatchedReceiver {
+            receiver,
+            progress: progress.clone(),
+        };
+        let handle = tokio::spawn(func(receiver));
+
+        AsyncWatchedTask {
+            sender,
+            progress,
+            handle,
+        }
+    }
+
+    /// The watched tasks that are stalled right now.
+    pub fn stalled(&self) -> Vec<Stall> {
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.retain(|progress| progress.strong_count() > 0);
+        tasks
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter_map(|progress| progress.stall(self.stall_after))
+            .collect()
+    }
+
+    /// Checks every `interval` and sends each stall to `recipient`. Stops
+    /// once the recipient is gone or all handles of the watchdog are
+    /// dropped. Must be called from within a tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub fn monitor(&self, interval: Duration, recipient: impl Into<Recipient<Stall>>) {
+        let recipient = recipient.into();
+        let stall_after = self.stall_after;
+        let tasks = Arc::downgrade(&self.tasks);
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            loop {
+                ticks.tick().await;
+                let Some(tasks) = tasks.upgrade() else {
+                    return;
+                };
+                let watchdog = Watchdog { stall_after, tasks };
+                for stall in watchdog.stalled() {
+                    if recipient.try_send(stall).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    fn watch(&self, name: String) -> Arc<Progress> {
+        let progress = Arc::new(Progress {
+            name,
+            sent: AtomicU64::new(0),
+            received: AtomicU64::new(0),
+            last_progress: Mutex::new(Instant::now()),
+        });
+        self.tasks.lock().unwrap().push(Arc::downgrade(&progress));
+        progress
+    }
+}
+
+pub struct WatchedTask<M, R> {
+    sender: Sender<M>,
+    progress: Arc<Progress>,
+    handle: JoinHandle<R>,
+}
+
+impl<M, R> WatchedTask<M, R> {
+    pub fn send(&self, payload: M) {
+        self.progress.sending();
+        self.sender
+            .send(payload)
+            .unwrap_or_else(|_| panic!("task mailbox is closed"))
+    }
+
+    pub fn join(self) -> R {
+        self.handle.join().unwrap()
+    }
+
+    pub fn drain(self) -> R {
+        drop(self.sender);
+        self.handle.join().unwrap()
+    }
+}
+
+pub struct WatchedReceiver<M> {
+    receiver: Receiver<M>,
+    progress: Arc<Progress>,
+}
+
+impl<M> WatchedReceiver<M> {
+    pub fn recv(&mut self) -> Result<M, RecvError> {
+        let payload = self.receiver.recv()?;
+        self.progress.received();
+        Ok(payload)
+    }
+
+    pub fn try_recv(&mut self) -> Result<M, TryRecvError> {
+        let payload = self.receiver.try_recv()?;
+        self.progress.received();
+        Ok(payload)
+    }
+}
+
+/// Async counterpart of [`WatchedTask`].
+#[cfg(feature = "tokio")]
+pub struct AsyncWatchedTask<M, R> {
+    sender: tokio::sync::mpsc::UnboundedSender<M>,
+    progress: Arc<Progress>,
+    handle: tokio::task::JoinHandle<R>,
+}
+
+#[cfg(feature = "tokio")]
+impl<M, R> AsyncWatchedTask<M, R> {
+    pub async fn send(&self, payload: M) {
+        self.progress.sending();
+        self.sender
+            .send(payload)
+            .unwrap_or_else(|_| panic!("task mailbox is closed"))
+    }
+
+    pub async fn join(self) -> R {
+        self.handle.await.unwrap()
+    }
+
+    pub async fn drain(self) -> R {
+        drop(self.sender);
+        self.handle.await.unwrap()
+    }
+}
+
+/// Async counterpart of [`WatchedReceiver`].
+#[cfg(feature = "tokio")]
+pub struct AsyncWatchedReceiver<M> {
+    receiver: tokio::sync::mpsc::UnboundedReceiver<M>,
+    progress: Arc<Progress>,
+}
+
+#[cfg(feature = "tokio")]
+impl<M> AsyncWatchedReceiver<M> {
+    pub async fn recv(&mut self) -> Option<M> {
+        let payload = self.receiver.recv().await?;
+        self.progress.received();
+        Some(payload)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watchdog_flags_tasks_stuck_with_pending_messages() {
+        let watchdog = Watchdog::new(Duration::from_millis(20));
+        let (unblock, blocked) = channel::<()>();
+        let stuck = watchdog.spawn_task("stuck", move |mut receiver: WatchedReceiver<u32>| {
+            receiver.recv().unwrap();
+            blocked.recv().unwrap();
+            while receiver.recv().is_ok() {}
+        });
+        let idle = watchdog.spawn_task(
+            "idle",
+            |mut receiver: WatchedReceiver<u32>| {
+                while receiver.recv().is_ok() {}
+            },
+        );
+
+        stuck.send(1);
+        stuck.send(2);
+        stuck.send(3);
+        std::thread::sleep(Duration::from_millis(50));
+
+        let stalled = watchdog.stalled();
+        assert_eq!(stalled.len(), 1);
+        assert_eq!(stalled[0].name, "stuck");
+        assert_eq!(stalled[0].pending, 2);
+        assert!(stalled[0].since >= Duration::from_millis(20));
+
+        unblock.send(()).unwrap();
+        stuck.drain();
+        idle.drain();
+        assert!(watchdog.stalled().is_empty());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_watchdog_monitor_reports_stalls() {
+        let watchdog = Watchdog::new(Duration::from_millis(20));
+        let (unblock, blocked) = tokio::sync::oneshot::channel::<()>();
+        let stuck = watchdog.spawn_async_task(
+            "stuck",
+            |mut receiver: AsyncWatchedReceiver<u32>| async move {
+                receiver.recv().await;
+                blocked.await.unwrap();
+                while receiver.recv().await.is_some() {}
+            },
+        );
+        let (reports, mut reported) = tokio::sync::mpsc::unbounded_channel();
+        watchdog.monitor(
+            Duration::from_millis(10),
+            Recipient::new(move |stall| reports.send(stall).map_err(|_| crate::Disconnected)),
+        );
+
+        stuck.send(1).await;
+        stuck.send(2).await;
+        let stall = reported.recv().await.unwrap();
+        assert_eq!((stall.name.as_str(), stall.pending), ("stuck", 1));
+
+        unblock.send(()).unwrap();
+        stuck.drain().await;
+    }
+}
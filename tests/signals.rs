@@ -0,0 +1,46 @@
+//! Sends real signals to the test process, so it gets a test binary of its
+//! own instead of sharing one with the unit tests.
+#![cfg(all(unix, feature = "signals"))]
+
+use notizia::{
+    signals::{Signal, subscribe},
+    spawn_async_task,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Message {
+    Signal(Signal),
+    Work(u32),
+}
+
+#[tokio::test]
+async fn test_signals_are_delivered_as_messages() {
+    let task = spawn_async_task(|mut receiver| async move {
+        let mut received = Vec::new();
+        while let Some(message) = receiver.recv().await {
+            let stop = matches!(message, Message::Signal(_));
+            received.push(message);
+            if stop {
+                break;
+            }
+        }
+        received
+    });
+    // only SIGHUP, the handler stays installed for good and SIGINT/SIGTERM
+    // should keep killing the test binary
+    let subscription = subscribe(&task, &[Signal::Hangup], Message::Signal).unwrap();
+
+    task.send(Message::Work(1)).await;
+    let status = std::process::Command::new("kill")
+        .args(["-HUP", &std::process::id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let received = task.join().await;
+    assert_eq!(
+        received,
+        vec![Message::Work(1), Message::Signal(Signal::Hangup)]
+    );
+    subscription.unsubscribe();
+}